@@ -0,0 +1,477 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::domain_name::DomainName;
+use crate::record::{Class, Content, Kind, Record};
+
+/// records are never kept longer than a week, whatever their ttl, RFC 8767 4
+const MAX_TTL: u32 = 604_800;
+/// negative answers are kept for at most three hours, RFC 2308 5
+const MAX_NEGATIVE_TTL: u32 = 10_800;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    name: DomainName,
+    slot: Slot,
+    class: Class,
+}
+
+/// what an entry holds for its name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Slot {
+    Kind(Kind),
+    /// every kind, as when a name does not exist
+    Every,
+    /// the referral from the parent zone, kept apart from answers
+    Delegation,
+}
+
+impl Key {
+    fn new(name: &DomainName, slot: Slot, class: Class) -> Key {
+        Key {
+            name: name.clone(),
+            slot,
+            class,
+        }
+    }
+}
+
+/// What the cache holds for a name, kind and class
+#[derive(Debug, Clone)]
+pub enum Cached {
+    Records(Vec<Record>),
+    /// the name exists but has no records of the kind, RFC 2308 2.2
+    NoData {
+        soa: Record,
+    },
+    /// the name does not exist at all, RFC 2308 2.1
+    NxDomain {
+        soa: Record,
+    },
+    /// the NS records a parent zone delegates a zone with, and the glue
+    /// addresses it gave for those within its own zone. Neither is an
+    /// authoritative answer, so lookups never return them
+    Delegation {
+        nameservers: Vec<Record>,
+        glue: Vec<Record>,
+    },
+}
+
+impl Cached {
+    /// every record held, with its ttl set to what remains
+    fn with_ttl(mut self, ttl: i32) -> Cached {
+        match &mut self {
+            Cached::Records(records) => records.iter_mut().for_each(|r| r.ttl = ttl),
+            Cached::NoData { soa } | Cached::NxDomain { soa } => soa.ttl = ttl,
+            Cached::Delegation { nameservers, glue } => nameservers
+                .iter_mut()
+                .chain(glue.iter_mut())
+                .for_each(|r| r.ttl = ttl),
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    cached: Cached,
+    expires: Instant,
+    /// when the entry was last read or written, for eviction
+    used: u64,
+}
+
+/// A cache of RRsets and negative answers, each kept for as long as the
+/// smallest ttl among its records, and evicting the least recently used
+/// entry when full
+#[derive(Debug, Clone)]
+pub struct Cache {
+    capacity: usize,
+    entries: HashMap<Key, Entry>,
+    /// entries by when they were last used, oldest first
+    order: BTreeMap<u64, Key>,
+    clock: u64,
+}
+
+impl Cache {
+    /// a cache holding up to `capacity` entries
+    pub fn new(capacity: usize) -> Cache {
+        Cache {
+            capacity,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+    /// The unexpired RRset for a name, with each ttl lowered by the time
+    /// it has spent in the cache
+    pub fn get(&mut self, name: &DomainName, kind: Kind, class: Class) -> Option<Vec<Record>> {
+        match self.lookup(name, kind, class)? {
+            Cached::Records(records) => Some(records),
+            _ => None,
+        }
+    }
+    /// Whatever is known about a name's records of a kind, positive or
+    /// negative, with ttls lowered by the time spent in the cache
+    pub fn lookup(&mut self, name: &DomainName, kind: Kind, class: Class) -> Option<Cached> {
+        self.fetch(Key::new(name, Slot::Every, class))
+            .or_else(|| self.fetch(Key::new(name, Slot::Kind(kind), class)))
+    }
+    fn fetch(&mut self, key: Key) -> Option<Cached> {
+        let entry = self.entries.get(&key)?;
+        let remaining = entry.expires.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            self.remove(&key);
+            return None;
+        }
+        let cached = entry.cached.clone().with_ttl(remaining.as_secs() as i32);
+        self.touch(key);
+        Some(cached)
+    }
+    /// Stores records grouped into RRsets, replacing any set already held
+    /// for the same name, kind and class. Sets with a ttl of zero are not
+    /// kept
+    pub fn insert(&mut self, records: &[Record]) {
+        let mut sets: Vec<(Key, Vec<Record>)> = Vec::new();
+        for record in records.iter() {
+            let key = Key::new(&record.name, Slot::Kind(record.kind), record.class);
+            match sets.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, set)) => set.push(record.clone()),
+                None => sets.push((key, vec![record.clone()])),
+            }
+        }
+        for (key, set) in sets {
+            let ttl = min_ttl(&set);
+            self.store(key, Cached::Records(set), ttl);
+        }
+    }
+    /// Remembers that a name has no records of a kind, for as long as the
+    /// SOA from the authority section allows
+    pub fn insert_nodata(&mut self, name: &DomainName, kind: Kind, class: Class, soa: &Record) {
+        let key = Key::new(name, Slot::Kind(kind), class);
+        let cached = Cached::NoData { soa: soa.clone() };
+        self.store(key, cached, negative_ttl(soa));
+    }
+    /// Remembers that a name does not exist, whatever the kind asked for
+    pub fn insert_nxdomain(&mut self, name: &DomainName, class: Class, soa: &Record) {
+        let key = Key::new(name, Slot::Every, class);
+        let cached = Cached::NxDomain { soa: soa.clone() };
+        self.store(key, cached, negative_ttl(soa));
+    }
+    /// Remembers the servers a zone was delegated to, for as long as the
+    /// shortest ttl among the NS and glue records. The caller is to have
+    /// dropped any glue from outside the zone of the server that sent it
+    pub fn insert_delegation(
+        &mut self,
+        zone: &DomainName,
+        class: Class,
+        nameservers: &[Record],
+        glue: &[Record],
+    ) {
+        let key = Key::new(zone, Slot::Delegation, class);
+        let ttl = min_ttl(&[nameservers, glue].concat());
+        let cached = Cached::Delegation {
+            nameservers: nameservers.to_vec(),
+            glue: glue.to_vec(),
+        };
+        self.store(key, cached, ttl);
+    }
+    fn store(&mut self, key: Key, cached: Cached, ttl: u32) {
+        if ttl == 0 || self.capacity == 0 {
+            return;
+        }
+        self.remove(&key);
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        let expires = Instant::now() + Duration::from_secs(ttl as u64);
+        self.entries.insert(
+            key.clone(),
+            Entry {
+                cached,
+                expires,
+                used: 0,
+            },
+        );
+        self.touch(key);
+    }
+    /// The closest zone enclosing a name, below the root, that was
+    /// delegated, with the addresses of its nameservers from glue or from
+    /// earlier answers. Zones whose nameservers have no cached address are
+    /// skipped
+    pub fn delegation(&mut self, name: &DomainName) -> Option<(DomainName, Vec<Record>)> {
+        let mut next = Some(name.clone());
+        while let Some(zone) = next.filter(|zone| !zone.is_root()) {
+            next = zone.parent();
+            let key = Key::new(&zone, Slot::Delegation, Class::Internet);
+            let Some(Cached::Delegation { nameservers, glue }) = self.fetch(key) else {
+                continue;
+            };
+            let answered: Vec<Record> = nameservers
+                .iter()
+                .filter_map(|record| match &record.data {
+                    Content::DomainName(ns) => Some(ns),
+                    _ => None,
+                })
+                .flat_map(|ns| {
+                    [Kind::A, Kind::AAAA]
+                        .into_iter()
+                        .filter_map(|kind| self.get(ns, kind, Class::Internet))
+                        .flatten()
+                        .collect::<Vec<Record>>()
+                })
+                .collect();
+            let addresses = [glue, answered].concat();
+            if !addresses.is_empty() {
+                return Some((zone, addresses));
+            }
+        }
+        None
+    }
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.used);
+        }
+    }
+    /// marks an entry as the most recently used
+    fn touch(&mut self, key: Key) {
+        let Some(entry) = self.entries.get_mut(&key) else {
+            return;
+        };
+        self.order.remove(&entry.used);
+        self.clock += 1;
+        entry.used = self.clock;
+        self.order.insert(self.clock, key);
+    }
+}
+
+/// the smallest ttl in a set, as the set may only be kept that long
+fn min_ttl(records: &[Record]) -> u32 {
+    // ttls above 2^31 - 1 are to be treated as zero, RFC 2181 8
+    let ttl = records
+        .iter()
+        .map(|r| r.ttl.max(0) as u32)
+        .min()
+        .unwrap_or(0);
+    ttl.min(MAX_TTL)
+}
+
+/// the lesser of the SOA's own ttl and its minimum field, RFC 2308 5
+fn negative_ttl(soa: &Record) -> u32 {
+    let minimum = match &soa.data {
+        Content::Soa(soa) => soa.minimum,
+        _ => 0,
+    };
+    (soa.ttl.max(0) as u32).min(minimum).min(MAX_NEGATIVE_TTL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::Soa;
+    use std::net::Ipv4Addr;
+
+    fn name(text: &str) -> DomainName {
+        text.parse().unwrap()
+    }
+
+    fn a(owner: &str, ttl: i32) -> Record {
+        Record {
+            name: name(owner),
+            kind: Kind::A,
+            class: Class::Internet,
+            ttl,
+            data: Content::IPv4(Ipv4Addr::new(192, 0, 2, 1)),
+        }
+    }
+
+    fn ns(owner: &str, target: &str) -> Record {
+        Record {
+            name: name(owner),
+            kind: Kind::NS,
+            class: Class::Internet,
+            ttl: 300,
+            data: Content::DomainName(name(target)),
+        }
+    }
+
+    fn soa(owner: &str, ttl: i32, minimum: u32) -> Record {
+        Record {
+            name: name(owner),
+            kind: Kind::SOA,
+            class: Class::Internet,
+            ttl,
+            data: Content::Soa(Soa {
+                minimum,
+                ..Soa::default()
+            }),
+        }
+    }
+
+    fn get(cache: &mut Cache, owner: &str) -> Option<Vec<Record>> {
+        cache.get(&name(owner), Kind::A, Class::Internet)
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = Cache::new(2);
+        cache.insert(&[a("a.", 300)]);
+        cache.insert(&[a("b.", 300)]);
+        cache.insert(&[a("c.", 300)]);
+        assert_eq!(cache.len(), 2);
+        assert!(get(&mut cache, "a.").is_none());
+        assert!(get(&mut cache, "b.").is_some());
+        assert!(get(&mut cache, "c.").is_some());
+    }
+
+    #[test]
+    fn get_refreshes_recency() {
+        let mut cache = Cache::new(2);
+        cache.insert(&[a("a.", 300)]);
+        cache.insert(&[a("b.", 300)]);
+        assert!(get(&mut cache, "a.").is_some());
+        cache.insert(&[a("c.", 300)]);
+        assert!(get(&mut cache, "a.").is_some());
+        assert!(get(&mut cache, "b.").is_none());
+    }
+
+    #[test]
+    fn replacing_a_set_does_not_evict() {
+        let mut cache = Cache::new(2);
+        cache.insert(&[a("a.", 300)]);
+        cache.insert(&[a("b.", 300)]);
+        cache.insert(&[a("a.", 600)]);
+        assert_eq!(cache.len(), 2);
+        assert!(get(&mut cache, "b.").is_some());
+    }
+
+    #[test]
+    fn sets_without_a_ttl_are_not_stored() {
+        let mut cache = Cache::new(8);
+        cache.insert(&[a("a.", 0)]);
+        // the set lives only as long as its shortest ttl
+        cache.insert(&[a("b.", 300), a("b.", 0)]);
+        // and a ttl with the top bit set counts as zero
+        cache.insert(&[a("c.", -1)]);
+        assert!(cache.is_empty());
+        assert!(Cache::new(0)
+            .get(&name("a."), Kind::A, Class::Internet)
+            .is_none());
+    }
+
+    #[test]
+    fn ttls_are_capped() {
+        let mut cache = Cache::new(8);
+        cache.insert(&[a("a.", i32::MAX)]);
+        let records = get(&mut cache, "a.").unwrap();
+        assert!(records[0].ttl <= MAX_TTL as i32);
+        cache.insert_nodata(
+            &name("a."),
+            Kind::MX,
+            Class::Internet,
+            &soa(".", 86400, 86400),
+        );
+        let Some(Cached::NoData { soa }) = cache.lookup(&name("a."), Kind::MX, Class::Internet)
+        else {
+            panic!("no negative entry");
+        };
+        assert!(soa.ttl <= MAX_NEGATIVE_TTL as i32);
+    }
+
+    #[test]
+    fn entries_count_down_and_expire() {
+        let mut cache = Cache::new(8);
+        cache.insert(&[a("short.", 1)]);
+        cache.insert(&[a("long.", 3)]);
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(get(&mut cache, "short.").is_none());
+        assert_eq!(cache.len(), 1);
+        let records = get(&mut cache, "long.").unwrap();
+        assert!(records[0].ttl < 3);
+    }
+
+    #[test]
+    fn negative_ttl_is_the_lesser_of_soa_ttl_and_minimum() {
+        assert_eq!(negative_ttl(&soa("example.", 60, 300)), 60);
+        assert_eq!(negative_ttl(&soa("example.", 3600, 300)), 300);
+        let mut cache = Cache::new(8);
+        cache.insert_nodata(&name("a."), Kind::A, Class::Internet, &soa(".", 0, 300));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn nxdomain_shadows_every_kind() {
+        let mut cache = Cache::new(8);
+        cache.insert(&[a("gone.", 300)]);
+        cache.insert_nodata(
+            &name("gone."),
+            Kind::MX,
+            Class::Internet,
+            &soa(".", 300, 300),
+        );
+        cache.insert_nxdomain(&name("gone."), Class::Internet, &soa(".", 300, 300));
+        for kind in [Kind::A, Kind::MX, Kind::TXT] {
+            let cached = cache.lookup(&name("gone."), kind, Class::Internet);
+            assert!(matches!(cached, Some(Cached::NxDomain { .. })), "{kind}");
+        }
+        assert!(get(&mut cache, "gone.").is_none());
+    }
+
+    #[test]
+    fn glue_is_not_an_answer() {
+        let mut cache = Cache::new(8);
+        let nameservers = [ns("example.", "ns.example.")];
+        let glue = [a("ns.example.", 300)];
+        cache.insert_delegation(&name("example."), Class::Internet, &nameservers, &glue);
+        assert!(get(&mut cache, "ns.example.").is_none());
+        assert!(cache
+            .lookup(&name("example."), Kind::NS, Class::Internet)
+            .is_none());
+        let (zone, addresses) = cache.delegation(&name("www.example.")).unwrap();
+        assert_eq!(zone, name("example."));
+        assert_eq!(addresses.len(), 1);
+    }
+
+    #[test]
+    fn delegation_skips_zones_without_addresses() {
+        let mut cache = Cache::new(8);
+        let com = [ns("com.", "a.gtld.net.")];
+        cache.insert_delegation(
+            &name("com."),
+            Class::Internet,
+            &com,
+            &[a("a.gtld.net.", 300)],
+        );
+        let example = [ns("example.com.", "ns.elsewhere.")];
+        cache.insert_delegation(&name("example.com."), Class::Internet, &example, &[]);
+        let (zone, _) = cache.delegation(&name("www.example.com.")).unwrap();
+        assert_eq!(zone, name("com."));
+        // until an answer gives the nameserver's address
+        cache.insert(&[a("ns.elsewhere.", 300)]);
+        let (zone, addresses) = cache.delegation(&name("www.example.com.")).unwrap();
+        assert_eq!(zone, name("example.com."));
+        assert_eq!(addresses[0].name, name("ns.elsewhere."));
+        assert!(cache.delegation(&name("www.example.org.")).is_none());
+    }
+
+    #[test]
+    fn root_is_never_a_delegation() {
+        let mut cache = Cache::new(8);
+        let root = [ns(".", "a.root-servers.net.")];
+        let glue = [a("a.root-servers.net.", 300)];
+        cache.insert_delegation(&DomainName::root(), Class::Internet, &root, &glue);
+        assert!(cache.delegation(&name("example.")).is_none());
+    }
+}
@@ -1,95 +1,189 @@
+use std::fmt::Display;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
-pub fn pop_u16(buf: &[u8], cursor: &mut usize) -> Option<u16> {
-    let hi = *buf.get(*cursor)? as u16;
-    *cursor += 1;
-    let lo = *buf.get(*cursor)? as u16;
-    *cursor += 1;
-    Some((hi << 8) | lo)
+/// Reasons a message can fail to parse, each carrying the offset into the
+/// message where the problem was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// the buffer ended before the item was complete
+    Truncated { offset: usize },
+    /// a compression pointer leads outside the message
+    BadPointer { offset: usize, pointer: usize },
+    /// a compression pointer does not lead strictly backwards, so could cycle
+    PointerLoop { offset: usize, pointer: usize },
+    /// a label whose top two bits are 0b01 or 0b10, which RFC 1035 4.1.4
+    /// reserves and RFC 6891 5 retires
+    ReservedLabelType { offset: usize, label_type: u8 },
+    /// a name over 255 octets, counted as if written without compression
+    NameTooLong { offset: usize, length: usize },
+    /// a name that follows more pointers than it could have labels
+    TooManyPointers { offset: usize },
+    /// the record data did not fill exactly RDLENGTH octets
+    RdLengthMismatch {
+        offset: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// a second OPT record in the additional section
+    DuplicateOpt { offset: usize },
+    /// an OPT record owned by a name other than the root
+    OptOwner { offset: usize },
+}
+
+impl ParseError {
+    pub fn offset(&self) -> usize {
+        use ParseError::*;
+        match self {
+            Truncated { offset }
+            | BadPointer { offset, .. }
+            | PointerLoop { offset, .. }
+            | ReservedLabelType { offset, .. }
+            | NameTooLong { offset, .. }
+            | TooManyPointers { offset }
+            | RdLengthMismatch { offset, .. }
+            | DuplicateOpt { offset }
+            | OptOwner { offset } => *offset,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ParseError::*;
+        match self {
+            Truncated { offset } => write!(f, "message truncated at offset {offset}"),
+            BadPointer { offset, pointer } => {
+                write!(
+                    f,
+                    "pointer at offset {offset} leads outside the message to {pointer}"
+                )
+            }
+            PointerLoop { offset, pointer } => {
+                write!(
+                    f,
+                    "pointer at offset {offset} does not lead backwards, to {pointer}"
+                )
+            }
+            ReservedLabelType { offset, label_type } => {
+                write!(
+                    f,
+                    "label at offset {offset} has reserved type {label_type:#04b}"
+                )
+            }
+            NameTooLong { offset, length } => {
+                write!(f, "name reaches {length} octets at offset {offset}")
+            }
+            TooManyPointers { offset } => {
+                write!(f, "name follows too many pointers at offset {offset}")
+            }
+            RdLengthMismatch {
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "record data at offset {offset} used {actual} octets instead of {expected}"
+            ),
+            DuplicateOpt { offset } => write!(f, "second OPT record at offset {offset}"),
+            OptOwner { offset } => {
+                write!(f, "OPT record at offset {offset} is not owned by the root")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn pop_u16(buf: &[u8], cursor: &mut usize) -> Result<u16, ParseError> {
+    let hi = pop_u8(buf, cursor)? as u16;
+    let lo = pop_u8(buf, cursor)? as u16;
+    Ok((hi << 8) | lo)
 }
 
-pub fn pop_u8(buf: &[u8], cursor: &mut usize) -> Option<u8> {
-    let lo = *buf.get(*cursor)?;
+pub fn pop_u8(buf: &[u8], cursor: &mut usize) -> Result<u8, ParseError> {
+    let lo = *buf
+        .get(*cursor)
+        .ok_or(ParseError::Truncated { offset: *cursor })?;
     *cursor += 1;
-    Some(lo)
+    Ok(lo)
+}
+
+/// a single length prefixed string of up to 255 bytes, RFC 1035 3.3
+pub fn pop_character_string(buf: &[u8], cursor: &mut usize) -> Result<Vec<u8>, ParseError> {
+    let mut c = *cursor;
+    let len = pop_u8(buf, &mut c)? as usize;
+    let string = pop_collection(buf, &mut c, len)?;
+    *cursor = c;
+    Ok(string)
 }
 
 pub fn pop_collection<T: FromBytes + Sized>(
     buf: &[u8],
     cursor: &mut usize,
     count: usize,
-) -> Option<Vec<T>> {
+) -> Result<Vec<T>, ParseError> {
     let mut c = *cursor;
-    let vec: Vec<T> = (0..count)
-        .map_while(|index| -> Option<T> {
-            let Some(item) = <T as FromBytes>::from_bytes(buf, &mut c) else {
-                println!("failed to extract element {}", index);
-                return None};
-            Some(item)
-        })
-        .collect();
+    let vec = (0..count)
+        .map(|_| <T as FromBytes>::from_bytes(buf, &mut c))
+        .collect::<Result<Vec<T>, ParseError>>()?;
     *cursor = c;
-    Some(vec)
+    Ok(vec)
 }
 
 pub trait FromBytes
 where
     Self: Sized,
 {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Self>;
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError>;
 }
 
 impl FromBytes for u8 {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Self> {
-        let byte = *buf.get(*cursor)?;
-        *cursor += 1;
-        Some(byte)
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        pop_u8(buf, cursor)
     }
 }
 
 impl FromBytes for char {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Self> {
-        let byte = *buf.get(*cursor)?;
-        let char = byte.try_into().ok()?;
-        *cursor += 1;
-        Some(char)
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        let byte = pop_u8(buf, cursor)?;
+        Ok(byte.into())
     }
 }
 
 impl FromBytes for i32 {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Self> {
-        let a = *buf.get(*cursor)? as u32;
-        *cursor += 1;
-        let b = *buf.get(*cursor)? as u32;
-        *cursor += 1;
-        let c = *buf.get(*cursor)? as u32;
-        *cursor += 1;
-        let d = *buf.get(*cursor)? as u32;
-        *cursor += 1;
-
-        let num = a << 24 | b << 16 | c << 8 | d;
-        Some(num as i32)
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        let num = u32::from_bytes(buf, cursor)?;
+        Ok(num as i32)
+    }
+}
+
+impl FromBytes for u32 {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        let mut cur = *cursor;
+        let a = pop_u8(buf, &mut cur)? as u32;
+        let b = pop_u8(buf, &mut cur)? as u32;
+        let c = pop_u8(buf, &mut cur)? as u32;
+        let d = pop_u8(buf, &mut cur)? as u32;
+        *cursor = cur;
+
+        Ok(a << 24 | b << 16 | c << 8 | d)
     }
 }
 
 impl FromBytes for Ipv4Addr {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Self> {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
         let mut cur = *cursor;
-        let a = *buf.get(cur)?;
-        cur += 1;
-        let b = *buf.get(cur)?;
-        cur += 1;
-        let c = *buf.get(cur)?;
-        cur += 1;
-        let d = *buf.get(cur)?;
-        cur += 1;
+        let a = pop_u8(buf, &mut cur)?;
+        let b = pop_u8(buf, &mut cur)?;
+        let c = pop_u8(buf, &mut cur)?;
+        let d = pop_u8(buf, &mut cur)?;
         *cursor = cur;
-        Some(Ipv4Addr::new(a, b, c, d))
+        Ok(Ipv4Addr::new(a, b, c, d))
     }
 }
 
 impl FromBytes for Ipv6Addr {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Self> {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
         let mut cur = *cursor;
         let a = pop_u16(buf, &mut cur)?;
         let b = pop_u16(buf, &mut cur)?;
@@ -100,6 +194,6 @@ impl FromBytes for Ipv6Addr {
         let g = pop_u16(buf, &mut cur)?;
         let h = pop_u16(buf, &mut cur)?;
         *cursor = cur;
-        Some(Ipv6Addr::new(a, b, c, d, e, f, g, h))
+        Ok(Ipv6Addr::new(a, b, c, d, e, f, g, h))
     }
 }
@@ -1,74 +1,600 @@
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
 
-use crate::deserialization::{pop_collection, pop_u8, FromBytes};
+use crate::deserialization::{pop_collection, pop_u8, FromBytes, ParseError};
+use crate::serialization::{push_u16, Compression, ToBytes};
 
-#[derive(Debug, Clone, Default)]
+/// the longest a single label may be, RFC 1035 2.3.4
+pub const MAX_LABEL: usize = 63;
+/// the longest a name may be in wire format, counting each label's length
+/// byte and the root label
+pub const MAX_NAME: usize = 255;
+
+/// A domain name as a list of labels, most specific first. Labels are
+/// arbitrary bytes, compared without regard to ascii case (RFC 4343).
+/// Whether a name was written fully qualified, ending in a dot, is kept
+/// for display but does not affect equality
+#[derive(Debug, Clone)]
 pub struct DomainName {
-    inner: String,
+    labels: Vec<Vec<u8>>,
+    fully_qualified: bool,
+}
+
+impl Default for DomainName {
+    fn default() -> Self {
+        DomainName::root()
+    }
 }
 
 impl DomainName {
-    pub fn new(name: &str) -> DomainName {
+    /// the root of the namespace, which has no labels
+    pub fn root() -> DomainName {
         DomainName {
-            inner: name.to_string(),
+            labels: vec![],
+            fully_qualified: true,
         }
     }
-    pub fn empty() -> DomainName {
-        DomainName::new("")
+    /// a fully qualified name from its labels, most specific first
+    pub fn from_labels<L: Into<Vec<u8>>>(
+        labels: impl IntoIterator<Item = L>,
+    ) -> Result<DomainName, NameError> {
+        let name = DomainName {
+            labels: labels.into_iter().map(Into::into).collect(),
+            fully_qualified: true,
+        };
+        name.validate()?;
+        Ok(name)
     }
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(self.inner.len());
-        let parts = self.inner.split('.');
-        for part in parts {
-            let len = part.len();
-            buf.push(len as u8);
-            buf.extend_from_slice(part.as_bytes());
+    fn validate(&self) -> Result<(), NameError> {
+        for label in self.labels.iter() {
+            if label.is_empty() {
+                return Err(NameError::EmptyLabel);
+            }
+            if label.len() > MAX_LABEL {
+                return Err(NameError::LabelTooLong {
+                    length: label.len(),
+                });
+            }
         }
-        buf.push(0);
-        buf
+        if self.wire_len() > MAX_NAME {
+            return Err(NameError::NameTooLong {
+                length: self.wire_len(),
+            });
+        }
+        Ok(())
+    }
+    pub fn labels(&self) -> &[Vec<u8>] {
+        &self.labels
+    }
+    /// the length of the name in uncompressed wire format
+    pub fn wire_len(&self) -> usize {
+        self.labels
+            .iter()
+            .map(|label| label.len() + 1)
+            .sum::<usize>()
+            + 1
+    }
+    pub fn is_root(&self) -> bool {
+        self.labels.is_empty()
+    }
+    /// whether the name ends at the root, rather than being relative to
+    /// some origin
+    pub fn is_fully_qualified(&self) -> bool {
+        self.fully_qualified
+    }
+    /// the name with its first label removed, or None for the root
+    pub fn parent(&self) -> Option<DomainName> {
+        let (_, rest) = self.labels.split_first()?;
+        Some(DomainName {
+            labels: rest.to_vec(),
+            fully_qualified: self.fully_qualified,
+        })
+    }
+    /// whether this name is `zone` or lies below it. Every name is a
+    /// subdomain of the root
+    pub fn is_subdomain_of(&self, zone: &DomainName) -> bool {
+        let Some(start) = self.labels.len().checked_sub(zone.labels.len()) else {
+            return false;
+        };
+        labels_eq(&self.labels[start..], &zone.labels)
+    }
+    /// this name followed by `origin`, as when completing a relative name.
+    /// Appending to a fully qualified name changes nothing
+    pub fn append(&self, origin: &DomainName) -> Result<DomainName, NameError> {
+        if self.fully_qualified {
+            return Ok(self.clone());
+        }
+        let name = DomainName {
+            labels: [self.labels.as_slice(), origin.labels.as_slice()].concat(),
+            fully_qualified: origin.fully_qualified,
+        };
+        name.validate()?;
+        Ok(name)
+    }
+    /// the name used to look up PTR records for an address, in
+    /// in-addr.arpa (RFC 1035 3.5) or ip6.arpa (RFC 3596 2.5)
+    pub fn reverse(ip: IpAddr) -> DomainName {
+        let mut labels: Vec<Vec<u8>> = match ip {
+            IpAddr::V4(ip) => ip
+                .octets()
+                .iter()
+                .rev()
+                .map(|o| o.to_string().into_bytes())
+                .collect(),
+            IpAddr::V6(ip) => ip
+                .octets()
+                .iter()
+                .rev()
+                .flat_map(|o| [o & 0xf, o >> 4])
+                .map(|nibble| format!("{nibble:x}").into_bytes())
+                .collect(),
+        };
+        let zone: &[u8] = if ip.is_ipv4() { b"in-addr" } else { b"ip6" };
+        labels.push(zone.to_vec());
+        labels.push(b"arpa".to_vec());
+        DomainName {
+            labels,
+            fully_qualified: true,
+        }
+    }
+    /// the address a reverse lookup name refers to, if this is one
+    pub fn reverse_address(&self) -> Option<IpAddr> {
+        let (rest, zone) = self.labels.split_at(self.labels.len().checked_sub(2)?);
+        let text: Vec<&str> = rest
+            .iter()
+            .rev()
+            .map(|label| std::str::from_utf8(label).ok())
+            .collect::<Option<_>>()?;
+        if labels_eq(zone, &[b"in-addr".to_vec(), b"arpa".to_vec()]) {
+            let octets: Vec<u8> = text
+                .iter()
+                .map(|o| decimal_octet(o))
+                .collect::<Option<_>>()?;
+            let octets: [u8; 4] = octets.try_into().ok()?;
+            Some(IpAddr::V4(Ipv4Addr::from(octets)))
+        } else if labels_eq(zone, &[b"ip6".to_vec(), b"arpa".to_vec()]) {
+            let nibbles: Vec<u8> = text
+                .iter()
+                .map(|n| match n.len() {
+                    1 => u8::from_str_radix(n, 16).ok(),
+                    _ => None,
+                })
+                .collect::<Option<_>>()?;
+            let nibbles: [u8; 32] = nibbles.try_into().ok()?;
+            let octets: Vec<u8> = nibbles
+                .chunks(2)
+                .map(|pair| pair[0] << 4 | pair[1])
+                .collect();
+            let octets: [u8; 16] = octets.try_into().ok()?;
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        } else {
+            None
+        }
+    }
+    /// replaces `suffix` at the end of this name with `target`, as when
+    /// synthesizing a CNAME from a DNAME. Returns None if this name is not
+    /// strictly below `suffix`, or the result would be too long
+    pub fn replace_suffix(&self, suffix: &DomainName, target: &DomainName) -> Option<DomainName> {
+        if self.labels.len() <= suffix.labels.len() || !self.is_subdomain_of(suffix) {
+            return None;
+        }
+        let prefix = &self.labels[..self.labels.len() - suffix.labels.len()];
+        let name = DomainName {
+            labels: [prefix, target.labels.as_slice()].concat(),
+            fully_qualified: true,
+        };
+        name.validate().ok()?;
+        Some(name)
+    }
+}
+
+/// an octet written in plain decimal, without sign or leading zeros
+fn decimal_octet(text: &str) -> Option<u8> {
+    let canonical = match text.as_bytes() {
+        [b'0'] => true,
+        [first, ..] => *first != b'0' && text.bytes().all(|b| b.is_ascii_digit()),
+        [] => false,
+    };
+    if !canonical {
+        return None;
+    }
+    text.parse().ok()
+}
+
+fn labels_eq(a: &[Vec<u8>], b: &[Vec<u8>]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_ignore_ascii_case(b))
+}
+
+impl PartialEq for DomainName {
+    fn eq(&self, other: &Self) -> bool {
+        labels_eq(&self.labels, &other.labels)
+    }
+}
+
+impl Eq for DomainName {}
+
+impl Hash for DomainName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for label in self.labels.iter() {
+            state.write_u8(label.len() as u8);
+            for byte in label.iter() {
+                state.write_u8(byte.to_ascii_lowercase());
+            }
+        }
+    }
+}
+
+/// Parses the presentation format, where a trailing dot marks a fully
+/// qualified name, `\.` is a dot within a label and `\DDD` is any byte
+impl FromStr for DomainName {
+    type Err = NameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "." {
+            return Ok(DomainName::root());
+        }
+        let mut labels = vec![];
+        let mut label = vec![];
+        let mut bytes = s.bytes();
+        let mut fully_qualified = false;
+        while let Some(byte) = bytes.next() {
+            fully_qualified = false;
+            match byte {
+                b'.' => {
+                    if label.is_empty() {
+                        return Err(NameError::EmptyLabel);
+                    }
+                    labels.push(std::mem::take(&mut label));
+                    fully_qualified = true;
+                }
+                b'\\' => {
+                    let escaped = bytes.next().ok_or(NameError::BadEscape)?;
+                    if escaped.is_ascii_digit() {
+                        let digits = [
+                            escaped,
+                            bytes.next().unwrap_or(0),
+                            bytes.next().unwrap_or(0),
+                        ];
+                        if !digits.iter().all(u8::is_ascii_digit) {
+                            return Err(NameError::BadEscape);
+                        }
+                        let value = digits
+                            .iter()
+                            .fold(0u16, |value, digit| value * 10 + (digit - b'0') as u16);
+                        label.push(u8::try_from(value).map_err(|_| NameError::BadEscape)?);
+                    } else {
+                        label.push(escaped);
+                    }
+                }
+                _ => label.push(byte),
+            }
+        }
+        if !fully_qualified {
+            if label.is_empty() {
+                return Err(NameError::EmptyLabel);
+            }
+            labels.push(label);
+        }
+        let name = DomainName {
+            labels,
+            fully_qualified,
+        };
+        name.validate()?;
+        Ok(name)
     }
 }
 
 impl Display for DomainName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        <String as Display>::fmt(&self.inner, f)
+        if self.is_root() {
+            return write!(f, ".");
+        }
+        for (index, label) in self.labels.iter().enumerate() {
+            if index > 0 {
+                write!(f, ".")?;
+            }
+            for byte in label.iter() {
+                match byte {
+                    b'.' | b'\\' | b'"' | b'(' | b')' | b';' => write!(f, "\\{}", *byte as char)?,
+                    0x21..=0x7e => write!(f, "{}", *byte as char)?,
+                    _ => write!(f, "\\{byte:03}")?,
+                }
+            }
+        }
+        if self.fully_qualified {
+            write!(f, ".")?;
+        }
+        Ok(())
+    }
+}
+
+impl ToBytes for DomainName {
+    fn to_bytes(&self, buf: &mut Vec<u8>, names: &mut Compression) {
+        for index in 0..self.labels.len() {
+            let suffix = DomainName {
+                labels: self.labels[index..].to_vec(),
+                fully_qualified: true,
+            };
+            if let Some(pointer) = names.find(&suffix) {
+                push_u16(buf, 0b1100_0000_0000_0000 | pointer);
+                return;
+            }
+            names.insert(&suffix, buf.len());
+            let label = &self.labels[index];
+            buf.push(label.len() as u8);
+            buf.extend_from_slice(label);
+        }
+        buf.push(0);
     }
 }
 
+/// a name has at most 127 labels, so never needs more pointers than that
+const MAX_POINTERS: usize = 127;
+
+/// Decodes a possibly compressed name from an untrusted message. Every
+/// pointer must lead before the start of the labels that led to it, so
+/// that decoding always ends, and the decoded name may not exceed 255
+/// octets however it was compressed
 impl FromBytes for DomainName {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Self> {
-        let max_cursor: usize = *cursor;
-        let mut parts = Vec::new();
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        let mut labels = Vec::new();
+        // counting the root label, which every name ends with
+        let mut length = 1;
+        let mut position = *cursor;
+        // where the labels currently being read began
+        let mut start = *cursor;
+        // the caller continues after the first pointer, if there is one
+        let mut end = None;
+        let mut pointers = 0;
         loop {
-            let len = pop_u8(buf, cursor)? as u16;
-            if len == 0 {
-                break;
-            } else if (len & 0b11000000) == 0b11000000 {
-                let lo = pop_u8(buf, cursor)? as u16;
-                let hi = (len & 0b00111111) << 8;
-                let mut pointer = (hi | lo) as usize;
-                if pointer < max_cursor {
-                    // recurse
-                    let DomainName { inner: ending } =
-                        <DomainName as FromBytes>::from_bytes(buf, &mut pointer)?;
-                    let mut start = parts.join(".");
-                    if start.is_empty() {
-                        start.push('.');
+            let offset = position;
+            let byte = pop_u8(buf, &mut position)?;
+            match byte >> 6 {
+                0b00 if byte == 0 => break,
+                0b00 => {
+                    length += byte as usize + 1;
+                    if length > MAX_NAME {
+                        return Err(ParseError::NameTooLong { offset, length });
+                    }
+                    labels.push(pop_collection::<u8>(buf, &mut position, byte as usize)?);
+                }
+                0b11 => {
+                    let lo = pop_u8(buf, &mut position)? as usize;
+                    let pointer = (byte as usize & 0b0011_1111) << 8 | lo;
+                    end.get_or_insert(position);
+                    if pointer >= buf.len() {
+                        return Err(ParseError::BadPointer { offset, pointer });
                     }
-                    start.push_str(&ending);
-                    return Some(DomainName { inner: start });
-                } else {
-                    // todo: should be an error
-                    return None;
+                    if pointer >= start {
+                        return Err(ParseError::PointerLoop { offset, pointer });
+                    }
+                    pointers += 1;
+                    if pointers > MAX_POINTERS {
+                        return Err(ParseError::TooManyPointers { offset });
+                    }
+                    position = pointer;
+                    start = pointer;
                 }
+                label_type => return Err(ParseError::ReservedLabelType { offset, label_type }),
             }
-            let string: String = pop_collection::<char>(buf, cursor, len as usize)?
-                .iter()
-                .collect();
-            parts.push(string);
         }
-        Some(DomainName {
-            inner: parts.join("."),
+        *cursor = end.unwrap_or(position);
+        Ok(DomainName {
+            labels,
+            fully_qualified: true,
         })
     }
 }
+
+/// Why text could not be read as a domain name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameError {
+    /// two dots in a row, or a leading dot
+    EmptyLabel,
+    LabelTooLong {
+        length: usize,
+    },
+    /// the whole name, in wire format, is over 255 octets
+    NameTooLong {
+        length: usize,
+    },
+    /// a backslash not followed by a character or three decimal digits
+    /// below 256
+    BadEscape,
+}
+
+impl Display for NameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use NameError::*;
+        match self {
+            EmptyLabel => write!(f, "empty label"),
+            LabelTooLong { length } => {
+                write!(f, "label of {length} octets is longer than {MAX_LABEL}")
+            }
+            NameTooLong { length } => {
+                write!(f, "name of {length} octets is longer than {MAX_NAME}")
+            }
+            BadEscape => write!(f, "invalid escape sequence"),
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(buf: &[u8], at: usize) -> Result<(DomainName, usize), ParseError> {
+        let mut cursor = at;
+        let name = DomainName::from_bytes(buf, &mut cursor)?;
+        Ok((name, cursor))
+    }
+
+    fn pointer(to: usize) -> [u8; 2] {
+        [0xc0 | (to >> 8) as u8, to as u8]
+    }
+
+    /// appends a label of 63 octets, then a pointer to `to` or the root
+    /// label, returning where it starts
+    fn long_label(buf: &mut Vec<u8>, to: Option<usize>) -> usize {
+        let start = buf.len();
+        buf.push(63);
+        buf.extend([b'a'; 63]);
+        match to {
+            Some(to) => buf.extend(pointer(to)),
+            None => buf.push(0),
+        }
+        start
+    }
+
+    #[test]
+    fn reverse_round_trips() {
+        for ip in ["192.0.2.1", "0.0.0.0", "2001:db8::1", "::"] {
+            let ip: IpAddr = ip.parse().unwrap();
+            assert_eq!(DomainName::reverse(ip).reverse_address(), Some(ip));
+        }
+        let name = DomainName::reverse("192.0.2.10".parse().unwrap());
+        assert_eq!(name.to_string(), "10.2.0.192.in-addr.arpa.");
+    }
+
+    #[test]
+    fn reverse_address_needs_canonical_octets() {
+        for text in [
+            "01.2.3.4.in-addr.arpa.",
+            "+1.2.3.4.in-addr.arpa.",
+            "256.2.3.4.in-addr.arpa.",
+            "2.3.4.in-addr.arpa.",
+        ] {
+            let name: DomainName = text.parse().unwrap();
+            assert_eq!(name.reverse_address(), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn reverse_address_needs_every_nibble() {
+        let full = DomainName::reverse("2001:db8::1".parse().unwrap()).to_string();
+        for text in ["1.2.3.ip6.arpa.", &full[2..], &format!("0.{full}")] {
+            let name: DomainName = text.parse().unwrap();
+            assert_eq!(name.reverse_address(), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn root_only() {
+        let (name, cursor) = decode(&[0], 0).unwrap();
+        assert!(name.is_root());
+        assert_eq!(cursor, 1);
+    }
+
+    #[test]
+    fn pointer_only() {
+        let (name, cursor) = decode(&[0, 0xc0, 0], 1).unwrap();
+        assert!(name.is_root());
+        assert_eq!(cursor, 3);
+    }
+
+    #[test]
+    fn labels_then_pointer() {
+        let buf = b"\x03com\x00\x03www\xc0\x00\x09";
+        let (name, cursor) = decode(buf, 5).unwrap();
+        assert_eq!(name.to_string(), "www.com.");
+        assert_eq!(cursor, 11);
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(
+            decode(b"\x03c", 0),
+            Err(ParseError::Truncated { offset: 2 })
+        );
+        assert_eq!(decode(&[0xc0], 0), Err(ParseError::Truncated { offset: 1 }));
+    }
+
+    #[test]
+    fn pointer_outside_message() {
+        let error = ParseError::BadPointer {
+            offset: 0,
+            pointer: 5,
+        };
+        assert_eq!(decode(&[0xc0, 5], 0), Err(error));
+    }
+
+    #[test]
+    fn pointer_to_itself() {
+        let error = ParseError::PointerLoop {
+            offset: 0,
+            pointer: 0,
+        };
+        assert_eq!(decode(&[0xc0, 0], 0), Err(error));
+    }
+
+    #[test]
+    fn pointer_back_into_its_own_labels() {
+        let error = ParseError::PointerLoop {
+            offset: 2,
+            pointer: 0,
+        };
+        assert_eq!(decode(b"\x01a\xc0\x00", 0), Err(error));
+    }
+
+    #[test]
+    fn pointer_forwards_after_pointing_back() {
+        let error = ParseError::PointerLoop {
+            offset: 2,
+            pointer: 4,
+        };
+        assert_eq!(decode(b"\x01a\xc0\x04\xc0\x00", 4), Err(error));
+    }
+
+    #[test]
+    fn reserved_label_types() {
+        let error = ParseError::ReservedLabelType {
+            offset: 0,
+            label_type: 0b01,
+        };
+        assert_eq!(decode(&[0x40, 0], 0), Err(error));
+        let error = ParseError::ReservedLabelType {
+            offset: 0,
+            label_type: 0b10,
+        };
+        assert_eq!(decode(&[0x80, 0], 0), Err(error));
+    }
+
+    #[test]
+    fn too_many_pointers() {
+        // a root label, then pointers that each lead to the one before
+        let mut buf = vec![0];
+        let mut last = 0;
+        for _ in 0..MAX_POINTERS {
+            let at = buf.len();
+            buf.extend(pointer(last));
+            last = at;
+        }
+        let (name, _) = decode(&buf, last).unwrap();
+        assert!(name.is_root());
+        buf.extend(pointer(last));
+        assert_eq!(
+            decode(&buf, buf.len() - 2),
+            Err(ParseError::TooManyPointers { offset: 1 })
+        );
+    }
+
+    #[test]
+    fn too_long_through_compression() {
+        let mut buf = vec![];
+        let first = long_label(&mut buf, None);
+        let second = long_label(&mut buf, Some(first));
+        let third = long_label(&mut buf, Some(second));
+        let (name, _) = decode(&buf, third).unwrap();
+        assert_eq!(name.wire_len(), 193);
+        let fourth = long_label(&mut buf, Some(third));
+        let error = ParseError::NameTooLong {
+            offset: first,
+            length: 257,
+        };
+        assert_eq!(decode(&buf, fourth), Err(error));
+    }
+}
@@ -0,0 +1,330 @@
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::deserialization::{pop_collection, pop_u16, FromBytes, ParseError};
+use crate::domain_name::DomainName;
+use crate::record::{Class, Content, Kind, Record};
+use crate::serialization::{push_u16, Compression, ToBytes};
+
+/// a payload size that avoids fragmentation on nearly every path, from
+/// DNS flag day 2020
+pub const DEFAULT_PAYLOAD_SIZE: u16 = 1232;
+
+/// the DO bit within the OPT record's ttl
+const DNSSEC_OK: u32 = 1 << 15;
+/// the flag bits after DO, RFC 6891 6.1.4
+const RESERVED_FLAGS: u32 = DNSSEC_OK - 1;
+
+/// The EDNS(0) extensions carried by a message's OPT pseudo-record, RFC 6891
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edns {
+    /// the largest udp response the sender can reassemble
+    pub udp_payload_size: u16,
+    /// the upper eight bits of the twelve bit response code
+    pub extended_rcode: u8,
+    pub version: u8,
+    /// the sender can handle DNSSEC records, RFC 3225
+    pub dnssec_ok: bool,
+    /// the other flag bits, to be zero but kept as received
+    pub reserved: u16,
+    pub options: Vec<EdnsOption>,
+}
+
+impl Default for Edns {
+    fn default() -> Self {
+        Edns {
+            udp_payload_size: DEFAULT_PAYLOAD_SIZE,
+            extended_rcode: 0,
+            version: 0,
+            dnssec_ok: false,
+            reserved: 0,
+            options: vec![],
+        }
+    }
+}
+
+impl Edns {
+    pub fn new() -> Edns {
+        Edns::default()
+    }
+    pub fn with_payload_size(mut self, size: u16) -> Edns {
+        self.udp_payload_size = size;
+        self
+    }
+    pub fn with_dnssec_ok(mut self) -> Edns {
+        self.dnssec_ok = true;
+        self
+    }
+    pub fn with_option(mut self, option: EdnsOption) -> Edns {
+        self.options.push(option);
+        self
+    }
+    /// reads the fields packed into an OPT record's class and ttl
+    pub fn from_record(record: &Record) -> Edns {
+        let ttl = record.ttl as u32;
+        let options = match &record.data {
+            Content::Opt(options) => options.clone(),
+            _ => vec![],
+        };
+        Edns {
+            udp_payload_size: u16::from(record.class),
+            extended_rcode: (ttl >> 24) as u8,
+            version: (ttl >> 16) as u8,
+            dnssec_ok: (ttl & DNSSEC_OK) == DNSSEC_OK,
+            reserved: (ttl & RESERVED_FLAGS) as u16,
+            options,
+        }
+    }
+    /// packs into an OPT record, which is always owned by the root
+    pub fn to_record(&self) -> Record {
+        let mut ttl = (self.extended_rcode as u32) << 24
+            | (self.version as u32) << 16
+            | (self.reserved as u32 & RESERVED_FLAGS);
+        if self.dnssec_ok {
+            ttl |= DNSSEC_OK;
+        }
+        Record {
+            name: DomainName::root(),
+            kind: Kind::OPT,
+            class: Class::from(self.udp_payload_size),
+            ttl: ttl as i32,
+            data: Content::Opt(self.options.clone()),
+        }
+    }
+}
+
+impl Display for Edns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "version {}, udp {}", self.version, self.udp_payload_size)?;
+        if self.dnssec_ok {
+            write!(f, ", do")?;
+        }
+        for option in self.options.iter() {
+            write!(f, ", {option}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Options carried in the data of an OPT record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EdnsOption {
+    /// the server's identifier, empty in queries, RFC 5001
+    Nsid(Vec<u8>),
+    /// the network a query was made on behalf of, RFC 7871
+    ClientSubnet(ClientSubnet),
+    /// RFC 7873
+    Cookie {
+        client: [u8; 8],
+        /// empty until the server has sent one
+        server: Vec<u8>,
+    },
+    /// zero bytes hiding the real size of a message, RFC 7830
+    Padding(usize),
+    /// RFC 8914
+    ExtendedError { info_code: u16, extra_text: String },
+    /// any other option, kept as is
+    Unknown { code: u16, data: Vec<u8> },
+}
+
+/// option codes, from the IANA EDNS0 option codes registry
+const NSID: u16 = 3;
+const CLIENT_SUBNET: u16 = 8;
+const COOKIE: u16 = 10;
+const PADDING: u16 = 12;
+const EXTENDED_ERROR: u16 = 15;
+
+impl EdnsOption {
+    pub fn code(&self) -> u16 {
+        match self {
+            EdnsOption::Nsid(_) => NSID,
+            EdnsOption::ClientSubnet(_) => CLIENT_SUBNET,
+            EdnsOption::Cookie { .. } => COOKIE,
+            EdnsOption::Padding(_) => PADDING,
+            EdnsOption::ExtendedError { .. } => EXTENDED_ERROR,
+            EdnsOption::Unknown { code, .. } => *code,
+        }
+    }
+    /// interprets the data of an option, keeping it unchanged unless it is
+    /// in the one form the typed option would write back
+    fn from_data(code: u16, data: Vec<u8>) -> EdnsOption {
+        let option = match code {
+            NSID => Some(EdnsOption::Nsid(data.clone())),
+            CLIENT_SUBNET => ClientSubnet::from_data(&data).map(EdnsOption::ClientSubnet),
+            COOKIE if data.len() == 8 || (16..=40).contains(&data.len()) => {
+                let mut client = [0u8; 8];
+                client.copy_from_slice(&data[..8]);
+                Some(EdnsOption::Cookie {
+                    client,
+                    server: data[8..].to_vec(),
+                })
+            }
+            PADDING if data.iter().all(|byte| *byte == 0) => Some(EdnsOption::Padding(data.len())),
+            EXTENDED_ERROR if data.len() >= 2 => {
+                String::from_utf8(data[2..].to_vec())
+                    .ok()
+                    .map(|extra_text| EdnsOption::ExtendedError {
+                        info_code: u16::from_be_bytes([data[0], data[1]]),
+                        extra_text,
+                    })
+            }
+            _ => None,
+        };
+        option.unwrap_or(EdnsOption::Unknown { code, data })
+    }
+    fn to_data(&self) -> Vec<u8> {
+        match self {
+            EdnsOption::Nsid(id) => id.clone(),
+            EdnsOption::ClientSubnet(subnet) => subnet.to_data(),
+            EdnsOption::Cookie { client, server } => [client.as_slice(), server].concat(),
+            EdnsOption::Padding(length) => vec![0; *length],
+            EdnsOption::ExtendedError {
+                info_code,
+                extra_text,
+            } => [info_code.to_be_bytes().as_slice(), extra_text.as_bytes()].concat(),
+            EdnsOption::Unknown { data, .. } => data.clone(),
+        }
+    }
+}
+
+impl FromBytes for EdnsOption {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        let mut c = *cursor;
+        let code = pop_u16(buf, &mut c)?;
+        let length = pop_u16(buf, &mut c)?;
+        let data = pop_collection(buf, &mut c, length as usize)?;
+        *cursor = c;
+        Ok(EdnsOption::from_data(code, data))
+    }
+}
+
+impl ToBytes for EdnsOption {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        let data = self.to_data();
+        push_u16(buf, self.code());
+        push_u16(buf, data.len() as u16);
+        buf.extend_from_slice(&data);
+    }
+}
+
+fn write_hex(f: &mut std::fmt::Formatter<'_>, bytes: &[u8]) -> std::fmt::Result {
+    for byte in bytes.iter() {
+        write!(f, "{byte:02x}")?;
+    }
+    Ok(())
+}
+
+impl Display for EdnsOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdnsOption::Nsid(id) => {
+                write!(f, "NSID ")?;
+                write_hex(f, id)
+            }
+            EdnsOption::ClientSubnet(subnet) => write!(f, "ECS {subnet}"),
+            EdnsOption::Cookie { client, server } => {
+                write!(f, "COOKIE ")?;
+                write_hex(f, client)?;
+                write_hex(f, server)
+            }
+            EdnsOption::Padding(length) => write!(f, "PADDING {length}"),
+            EdnsOption::ExtendedError {
+                info_code,
+                extra_text,
+            } => write!(f, "EDE {info_code} {extra_text:?}"),
+            EdnsOption::Unknown { code, data } => {
+                write!(f, "OPT{code} ")?;
+                write_hex(f, data)
+            }
+        }
+    }
+}
+
+/// The client subnet option, RFC 7871 6
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSubnet {
+    /// how many leading bits of the address are significant
+    pub source_prefix: u8,
+    /// how many leading bits the answer covers, zero in queries
+    pub scope_prefix: u8,
+    pub address: IpAddr,
+}
+
+/// address families, from the IANA address family numbers registry
+const FAMILY_IPV4: u16 = 1;
+const FAMILY_IPV6: u16 = 2;
+
+impl ClientSubnet {
+    pub fn new(address: IpAddr, source_prefix: u8) -> ClientSubnet {
+        ClientSubnet {
+            source_prefix,
+            scope_prefix: 0,
+            address,
+        }
+    }
+    /// reads the option only if the address is as long as the source
+    /// prefix requires and the bits past it are zero, RFC 7871 6
+    fn from_data(data: &[u8]) -> Option<ClientSubnet> {
+        let family = u16::from_be_bytes([*data.first()?, *data.get(1)?]);
+        let source_prefix = *data.get(2)?;
+        let scope_prefix = *data.get(3)?;
+        let bytes = &data[4..];
+        if bytes.len() != (source_prefix as usize).div_ceil(8) {
+            return None;
+        }
+        let spare = bytes.len() * 8 - source_prefix as usize;
+        if bytes
+            .last()
+            .is_some_and(|last| last & !(0xff << spare) != 0)
+        {
+            return None;
+        }
+        let address = match family {
+            FAMILY_IPV4 if bytes.len() <= 4 => {
+                let mut octets = [0u8; 4];
+                octets[..bytes.len()].copy_from_slice(bytes);
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            FAMILY_IPV6 if bytes.len() <= 16 => {
+                let mut octets = [0u8; 16];
+                octets[..bytes.len()].copy_from_slice(bytes);
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => return None,
+        };
+        Some(ClientSubnet {
+            source_prefix,
+            scope_prefix,
+            address,
+        })
+    }
+    /// only as many bytes of the address as the source prefix covers are sent
+    fn to_data(&self) -> Vec<u8> {
+        let (family, octets) = match self.address {
+            IpAddr::V4(ip) => (FAMILY_IPV4, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (FAMILY_IPV6, ip.octets().to_vec()),
+        };
+        let length = (self.source_prefix as usize).div_ceil(8).min(octets.len());
+        let mut data = Vec::with_capacity(4 + length);
+        push_u16(&mut data, family);
+        data.push(self.source_prefix);
+        data.push(self.scope_prefix);
+        data.extend_from_slice(&octets[..length]);
+        // bits past the prefix must be zero
+        let spare = length * 8 - (self.source_prefix as usize).min(length * 8);
+        if let (Some(last), 1..=7) = (data.last_mut(), spare) {
+            *last &= 0xff << spare;
+        }
+        data
+    }
+}
+
+impl Display for ClientSubnet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{}/{}",
+            self.address, self.source_prefix, self.scope_prefix
+        )
+    }
+}
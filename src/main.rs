@@ -1,23 +1,42 @@
 use std::env;
-use std::net::{UdpSocket, Ipv4Addr};
+use std::net::IpAddr;
 
-use weekend_dns::{packet::*, resolve};
 use weekend_dns::record::Kind;
-use weekend_dns::ROOT_SERVERS;
-
-const ROOT_SERVER: Ipv4Addr = ROOT_SERVERS[0].1;
+use weekend_dns::{resolve, reverse};
 
 fn main() {
     let mut args = env::args();
     let _ = args.next();
     let domain_str = args.next().unwrap_or("www.google.com".to_string());
 
+    if let Ok(ip) = domain_str.parse::<IpAddr>() {
+        println!("requesting names for {}", ip);
+        match reverse(ip) {
+            Ok(names) => {
+                for name in names.iter() {
+                    println!("{name}");
+                }
+            }
+            Err(e) => println!("lookup failed: {e}"),
+        }
+        return;
+    }
+
     let record_kind: Kind = args
         .next()
-        .and_then(|s| s.parse::<u16>().ok().and_then(|n| n.try_into().ok()))
+        .and_then(|s| match s.parse::<u16>() {
+            Ok(n) => Some(Kind::from(n)),
+            Err(_) => s.parse::<Kind>().ok(),
+        })
         .unwrap_or(Kind::A);
 
     println!("requesting address for {}", domain_str);
-    let address = resolve(&domain_str, record_kind);
-    println!("got {:?}", address);
+    match resolve(&domain_str, record_kind) {
+        Ok(resolution) => {
+            for record in resolution.chain.iter().chain(resolution.records.iter()) {
+                println!("{record}");
+            }
+        }
+        Err(e) => println!("lookup failed: {e}"),
+    }
 }
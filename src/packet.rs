@@ -1,42 +1,331 @@
 use rand::Rng;
 use std::fmt::Display;
-use std::net::Ipv4Addr;
 
-use crate::deserialization::{pop_collection, pop_u16, FromBytes};
-use crate::domain_name::DomainName;
+use crate::deserialization::{pop_collection, pop_u16, FromBytes, ParseError};
+use crate::domain_name::{DomainName, NameError};
+use crate::edns::Edns;
 use crate::record::Record;
 use crate::record::{Class, Kind};
-use crate::serialization::push_u16;
+use crate::serialization::{push_collection, push_u16, Compression, ToBytes};
 
+/// bit positions of the single bit flags, RFC 1035 4.1.1 and RFC 4035 3.2
+const QR: u16 = 1 << 15;
+const AA: u16 = 1 << 10;
+const TC: u16 = 1 << 9;
+const RD: u16 = 1 << 8;
+const RA: u16 = 1 << 7;
+const Z: u16 = 1 << 6;
+const AD: u16 = 1 << 5;
+const CD: u16 = 1 << 4;
+const OPCODE_SHIFT: u16 = 11;
+const OPCODE_MASK: u16 = 0b1111 << OPCODE_SHIFT;
+const RCODE_MASK: u16 = 0b1111;
 
-
-#[derive(Default, Clone, Copy)]
+/// The second 16 bits of the header
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Flags(u16);
 
 impl Flags {
     pub fn new() -> Flags {
         Flags(0)
     }
+    pub fn from_bits(bits: u16) -> Flags {
+        Flags(bits)
+    }
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
     pub fn with_recusion(mut self) -> Flags {
-        self.0 |= 1 << 8;
+        self.set_recursion_desired(true);
         self
     }
+    fn get(&self, bit: u16) -> bool {
+        (self.0 & bit) == bit
+    }
+    fn set(&mut self, bit: u16, value: bool) {
+        if value {
+            self.0 |= bit;
+        } else {
+            self.0 &= !bit;
+        }
+    }
+    /// QR, set in responses and clear in queries
+    pub fn is_response(&self) -> bool {
+        self.get(QR)
+    }
+    pub fn set_response(&mut self, value: bool) {
+        self.set(QR, value)
+    }
+    pub fn opcode(&self) -> Opcode {
+        Opcode::from(((self.0 & OPCODE_MASK) >> OPCODE_SHIFT) as u8)
+    }
+    pub fn set_opcode(&mut self, opcode: Opcode) {
+        let bits = (u8::from(opcode) as u16) << OPCODE_SHIFT;
+        self.0 = (self.0 & !OPCODE_MASK) | (bits & OPCODE_MASK);
+    }
+    /// AA, the responding server is an authority for the question
+    pub fn is_authoritative(&self) -> bool {
+        self.get(AA)
+    }
+    pub fn set_authoritative(&mut self, value: bool) {
+        self.set(AA, value)
+    }
+    /// TC, the message was cut short to fit the transport
+    pub fn is_truncated(&self) -> bool {
+        self.get(TC)
+    }
+    pub fn set_truncated(&mut self, value: bool) {
+        self.set(TC, value)
+    }
+    /// RD, asks the server to pursue the query recursively
+    pub fn recursion_desired(&self) -> bool {
+        self.get(RD)
+    }
+    pub fn set_recursion_desired(&mut self, value: bool) {
+        self.set(RD, value)
+    }
+    /// RA, the server supports recursive queries
+    pub fn recursion_available(&self) -> bool {
+        self.get(RA)
+    }
+    pub fn set_recursion_available(&mut self, value: bool) {
+        self.set(RA, value)
+    }
+    /// Z, reserved and should always be clear
+    pub fn reserved(&self) -> bool {
+        self.get(Z)
+    }
+    /// AD, all data in the response was authenticated by the server
+    pub fn authentic_data(&self) -> bool {
+        self.get(AD)
+    }
+    pub fn set_authentic_data(&mut self, value: bool) {
+        self.set(AD, value)
+    }
+    /// CD, asks the server not to perform DNSSEC validation
+    pub fn checking_disabled(&self) -> bool {
+        self.get(CD)
+    }
+    pub fn set_checking_disabled(&mut self, value: bool) {
+        self.set(CD, value)
+    }
+    pub fn rcode(&self) -> Rcode {
+        Rcode::from(self.0 & RCODE_MASK)
+    }
+    /// only the low four bits of the rcode fit in the header
+    pub fn set_rcode(&mut self, rcode: Rcode) {
+        self.0 = (self.0 & !RCODE_MASK) | (u16::from(rcode) & RCODE_MASK);
+    }
+}
+
+fn flag_write(
+    f: &mut std::fmt::Formatter<'_>,
+    set: bool,
+    zero_label: &str,
+    one_label: &str,
+) -> std::fmt::Result {
+    let label = if set { one_label } else { zero_label };
+    write!(f, "{label}")
+}
+
+impl Display for Flags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        flag_write(f, self.is_response(), "Q-", "R-")?;
+        write!(f, "{}-", self.opcode())?;
+        flag_write(f, self.is_authoritative(), "aa-", "AA-")?;
+        flag_write(f, self.is_truncated(), "tc-", "TC-")?;
+        flag_write(f, self.recursion_desired(), "rd-", "RD-")?;
+        flag_write(f, self.recursion_available(), "ra-", "RA-")?;
+        flag_write(f, self.reserved(), "z-", "Z-")?;
+        flag_write(f, self.authentic_data(), "ad-", "AD-")?;
+        flag_write(f, self.checking_disabled(), "cd-", "CD-")?;
+        write!(f, "{}", self.rcode())
+    }
+}
+
+impl ToBytes for Flags {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        push_u16(buf, self.0);
+    }
+}
+
+impl FromBytes for Flags {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Flags, ParseError> {
+        pop_u16(buf, cursor).map(Flags)
+    }
+}
+
+/// The kind of query, RFC 1035 4.1.1 with later additions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    /// a standard query
+    #[default]
+    Query,
+    /// an inverse query (Obsolete)
+    IQuery,
+    /// a server status request
+    Status,
+    /// zone change notification, RFC 1996
+    Notify,
+    /// dynamic update, RFC 2136
+    Update,
+    /// any other four bit value
+    Unassigned(u8),
+}
+
+impl From<u8> for Opcode {
+    fn from(value: u8) -> Self {
+        use Opcode::*;
+        match value {
+            0 => Query,
+            1 => IQuery,
+            2 => Status,
+            4 => Notify,
+            5 => Update,
+            n => Unassigned(n),
+        }
+    }
+}
+
+impl From<Opcode> for u8 {
+    fn from(value: Opcode) -> Self {
+        use Opcode::*;
+        match value {
+            Query => 0,
+            IQuery => 1,
+            Status => 2,
+            Notify => 4,
+            Update => 5,
+            Unassigned(n) => n,
+        }
+    }
+}
+
+impl Display for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Opcode::*;
+        match self {
+            Query => write!(f, "QUERY"),
+            IQuery => write!(f, "IQUERY"),
+            Status => write!(f, "STATUS"),
+            Notify => write!(f, "NOTIFY"),
+            Update => write!(f, "UPDATE"),
+            Unassigned(n) => write!(f, "OPCODE{n}"),
+        }
+    }
+}
+
+/// The response code, RFC 1035 4.1.1 with later additions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Rcode {
+    /// no error condition
+    #[default]
+    NoError,
+    /// the server was unable to interpret the query
+    FormErr,
+    /// the server was unable to process the query
+    ServFail,
+    /// the domain name does not exist
+    NXDomain,
+    /// the server does not support the kind of query
+    NotImp,
+    /// the server refuses to perform the operation
+    Refused,
+    /// a name exists when it should not, RFC 2136
+    YXDomain,
+    /// an RRset exists when it should not, RFC 2136
+    YXRRSet,
+    /// an RRset that should exist does not, RFC 2136
+    NXRRSet,
+    /// the server is not authoritative for the zone, RFC 2136
+    NotAuth,
+    /// a name is not within the zone, RFC 2136
+    NotZone,
+    /// the server does not support the EDNS version, RFC 6891
+    BadVers,
+    /// any other value
+    Unassigned(u16),
+}
+
+impl From<u16> for Rcode {
+    fn from(value: u16) -> Self {
+        use Rcode::*;
+        match value {
+            0 => NoError,
+            1 => FormErr,
+            2 => ServFail,
+            3 => NXDomain,
+            4 => NotImp,
+            5 => Refused,
+            6 => YXDomain,
+            7 => YXRRSet,
+            8 => NXRRSet,
+            9 => NotAuth,
+            10 => NotZone,
+            16 => BadVers,
+            n => Unassigned(n),
+        }
+    }
+}
+
+impl From<Rcode> for u16 {
+    fn from(value: Rcode) -> Self {
+        use Rcode::*;
+        match value {
+            NoError => 0,
+            FormErr => 1,
+            ServFail => 2,
+            NXDomain => 3,
+            NotImp => 4,
+            Refused => 5,
+            YXDomain => 6,
+            YXRRSet => 7,
+            NXRRSet => 8,
+            NotAuth => 9,
+            NotZone => 10,
+            BadVers => 16,
+            Unassigned(n) => n,
+        }
+    }
+}
+
+impl Display for Rcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Rcode::*;
+        match self {
+            NoError => write!(f, "NOERROR"),
+            FormErr => write!(f, "FORMERR"),
+            ServFail => write!(f, "SERVFAIL"),
+            NXDomain => write!(f, "NXDOMAIN"),
+            NotImp => write!(f, "NOTIMP"),
+            Refused => write!(f, "REFUSED"),
+            YXDomain => write!(f, "YXDOMAIN"),
+            YXRRSet => write!(f, "YXRRSET"),
+            NXRRSet => write!(f, "NXRRSET"),
+            NotAuth => write!(f, "NOTAUTH"),
+            NotZone => write!(f, "NOTZONE"),
+            BadVers => write!(f, "BADVERS"),
+            Unassigned(n) => write!(f, "RCODE{n}"),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct Packet {
     pub id: u16,
-    pub flags: u16,
+    pub flags: Flags,
     pub questions: Vec<Question>,
     pub answers: Vec<Record>,
     pub authorities: Vec<Record>,
+    /// kept apart from the additional records it is carried with
+    pub edns: Option<Edns>,
     pub additionals: Vec<Record>,
 }
 
 impl Packet {
     pub fn new() -> Packet {
         let id = rand::thread_rng().gen();
-        let flags = 0;
+        let flags = Flags::new();
         Packet {
             id,
             flags,
@@ -44,11 +333,12 @@ impl Packet {
             answers: vec![],
             authorities: vec![],
             additionals: vec![],
+            edns: None,
         }
     }
 
     pub fn with_flags(mut self, flags: Flags) -> Packet {
-        self.flags = flags.0;
+        self.flags = flags;
         self
     }
     pub fn with_question(mut self, question: Question) -> Packet {
@@ -68,25 +358,66 @@ impl Packet {
     //         additionals: vec![],
     //     }
     // }
+    pub fn with_edns(mut self, edns: Edns) -> Packet {
+        self.edns = Some(edns);
+        self
+    }
+    /// the full response code, including the upper bits carried by EDNS
+    pub fn rcode(&self) -> Rcode {
+        let upper = self
+            .edns
+            .as_ref()
+            .map(|edns| edns.extended_rcode)
+            .unwrap_or(0);
+        Rcode::from((upper as u16) << 4 | u16::from(self.flags.rcode()))
+    }
+    /// sets the response code, adding EDNS if it does not fit in the header
+    pub fn set_rcode(&mut self, rcode: Rcode) {
+        let value = u16::from(rcode);
+        self.flags.set_rcode(rcode);
+        if value > 0b1111 || self.edns.is_some() {
+            let edns = self.edns.get_or_insert_with(Edns::new);
+            edns.extended_rcode = (value >> 4) as u8;
+        }
+    }
     pub fn with_id(mut self, id: u16) -> Packet {
         self.id = id;
         self
     }
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buf = Vec::new();
-        push_u16(&mut buf, self.id);
-        push_u16(&mut buf, self.flags);
-        push_u16(&mut buf, self.questions.len() as u16);
-        push_u16(&mut buf, 0);
-        push_u16(&mut buf, 0);
-        push_u16(&mut buf, 0);
-
-        for question in self.questions.iter() {
-            buf.extend_from_slice(&question.to_bytes());
+        let mut names = Compression::new();
+        let header = Header {
+            id: self.id,
+            flags: self.flags,
+            questions: self.questions.len() as u16,
+            answers: self.answers.len() as u16,
+            authorities: self.authorities.len() as u16,
+            additionals: (self.additionals.len() + self.edns.iter().count()) as u16,
+        };
+        header.to_bytes(&mut buf, &mut names);
+        push_collection(&mut buf, &self.questions, &mut names);
+        push_collection(&mut buf, &self.answers, &mut names);
+        push_collection(&mut buf, &self.authorities, &mut names);
+        push_collection(&mut buf, &self.additionals, &mut names);
+        if let Some(edns) = &self.edns {
+            edns.to_record().to_bytes(&mut buf, &mut names);
         }
         buf
     }
-    pub fn from_bytes(buf: &[u8]) -> Option<Packet> {
+    /// checks a response carries the id and echoes the questions of a query,
+    /// anything else may be a spoofing attempt and should be discarded
+    pub fn is_response_to(&self, query: &Packet) -> bool {
+        self.flags.is_response()
+            && self.id == query.id
+            && self.questions.len() == query.questions.len()
+            && self
+                .questions
+                .iter()
+                .zip(query.questions.iter())
+                .all(|(a, b)| a.matches(b))
+    }
+    pub fn from_bytes(buf: &[u8]) -> Result<Packet, ParseError> {
         let mut cursor = 0;
         let header = Header::from_bytes(buf, &mut cursor)?;
 
@@ -101,65 +432,47 @@ impl Packet {
         let questions = pop_collection(buf, &mut cursor, questions as usize)?;
         let answers = pop_collection(buf, &mut cursor, answers as usize)?;
         let authorities = pop_collection(buf, &mut cursor, authorities as usize)?;
-        let additionals = pop_collection(buf, &mut cursor, additionals as usize)?;
+        let mut edns = None;
+        let mut records = Vec::with_capacity(additionals as usize);
+        for _ in 0..additionals {
+            let offset = cursor;
+            let record = Record::from_bytes(buf, &mut cursor)?;
+            if record.kind != Kind::OPT {
+                records.push(record);
+                continue;
+            }
+            // either is grounds for FORMERR, RFC 6891 6.1.1
+            if edns.is_some() {
+                return Err(ParseError::DuplicateOpt { offset });
+            }
+            if !record.name.is_root() {
+                return Err(ParseError::OptOwner { offset });
+            }
+            edns = Some(Edns::from_record(&record));
+        }
+        let additionals = records;
 
-        Some(Packet {
+        Ok(Packet {
             id,
             flags,
             questions,
             answers,
             authorities,
+            edns,
             additionals,
         })
     }
 }
 
-fn flag_write(
-    f: &mut std::fmt::Formatter<'_>,
-    flags: &u16,
-    offset: usize,
-    zero_label: &str,
-    one_label: &str,
-) -> std::fmt::Result {
-    let label = if (flags & (0b1 << offset)) == (0b1 << offset) {
-        one_label
-    } else {
-        zero_label
-    };
-    write!(f, "{label}")
-}
-
 impl Display for Packet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Packet#{:x} (", self.id)?;
-        {
-            flag_write(f, &self.flags, 0, "Q-", "R-")?;
-            // if  (self.flags & (1 << 0)) == (1 << 0) {
-            //     write!(f, "Qr ")?;
-            // } else {
-            //     write!(f, "qR ")?;
-            // }
-
-            let opcode = (self.flags >> 1) & 0b1111;
-            write!(f, "{opcode:02x}-")?;
-
-            flag_write(f, &self.flags, 5, "aa-", "AA-")?;
-            flag_write(f, &self.flags, 6, "tc-", "TC-")?;
-            flag_write(f, &self.flags, 7, "rd-", "RD-")?;
-            flag_write(f, &self.flags, 8, "ra-", "RA-")?;
-            flag_write(f, &self.flags, 9, "z-", "Z-")?;
-            flag_write(f, &self.flags, 10, "ad-", "AD-")?;
-            flag_write(f, &self.flags, 11, "cd-", "CD-")?;
-
-            let rcode = (self.flags >> 12) & 0b1111;
-            write!(f, "{rcode:02x}")?;
-
-            writeln!(f, ")")?;
-        }
+        writeln!(f, "{})", self.flags)?;
         if self.questions.is_empty()
             && self.answers.is_empty()
             && self.authorities.is_empty()
             && self.additionals.is_empty()
+            && self.edns.is_none()
         {
             write!(f, "Empty Packet")?;
         }
@@ -187,39 +500,43 @@ impl Display for Packet {
                 writeln!(f, "\t\t{}", q)?;
             }
         }
+        if let Some(edns) = &self.edns {
+            writeln!(f, "\tEDNS: {}", edns)?;
+        }
         writeln!(f)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct Header {
-    id: u16,
-    flags: u16,
-    questions: u16,
-    answers: u16,
-    authorities: u16,
-    additionals: u16,
+    pub id: u16,
+    pub flags: Flags,
+    pub questions: u16,
+    pub answers: u16,
+    pub authorities: u16,
+    pub additionals: u16,
 }
 
-impl Header {
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(12);
-        push_u16(&mut buf, self.id);
-        push_u16(&mut buf, self.flags);
-        push_u16(&mut buf, self.questions);
-        push_u16(&mut buf, self.answers);
-        push_u16(&mut buf, self.authorities);
-        push_u16(&mut buf, self.additionals);
-        buf
+impl ToBytes for Header {
+    fn to_bytes(&self, buf: &mut Vec<u8>, names: &mut Compression) {
+        push_u16(buf, self.id);
+        self.flags.to_bytes(buf, names);
+        push_u16(buf, self.questions);
+        push_u16(buf, self.answers);
+        push_u16(buf, self.authorities);
+        push_u16(buf, self.additionals);
     }
-    pub fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Header> {
+}
+
+impl FromBytes for Header {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Header, ParseError> {
         let id = pop_u16(buf, cursor)?;
-        let flags = pop_u16(buf, cursor)?;
+        let flags = Flags::from_bytes(buf, cursor)?;
         let questions = pop_u16(buf, cursor)?;
         let answers = pop_u16(buf, cursor)?;
         let authorities = pop_u16(buf, cursor)?;
         let additionals = pop_u16(buf, cursor)?;
-        Some(Header {
+        Ok(Header {
             id,
             flags,
             questions,
@@ -240,32 +557,45 @@ pub struct Question {
 impl Question {
     pub fn new() -> Question {
         Question {
-            name: DomainName::empty(),
+            name: DomainName::root(),
             kind: Kind::A,
             class: Class::Internet,
         }
     }
-    pub fn with_domain_name(mut self, name: &str) -> Question {
-        self.name = DomainName::new(name);
+    pub fn with_domain_name(mut self, name: &str) -> Result<Question, NameError> {
+        self.name = name.parse()?;
+        Ok(self)
+    }
+    pub fn with_name(mut self, name: DomainName) -> Question {
+        self.name = name;
         self
     }
     pub fn with_kind(mut self, kind: Kind) -> Question {
         self.kind = kind;
         self
     }
-    pub fn build(name: &str, kind: Kind) -> Question {
-        let name = DomainName::new(name);
-        Question {
-            name,
+    pub fn with_class(mut self, class: Class) -> Question {
+        self.class = class;
+        self
+    }
+    /// compares with another question, ignoring the case of the names
+    pub fn matches(&self, other: &Question) -> bool {
+        self.name == other.name && self.kind == other.kind && self.class == other.class
+    }
+    pub fn build(name: &str, kind: Kind) -> Result<Question, NameError> {
+        Ok(Question {
+            name: name.parse()?,
             kind,
             class: Class::Internet,
-        }
+        })
     }
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf: Vec<u8> = self.name.to_bytes();
-        push_u16(&mut buf, self.kind as u16);
-        push_u16(&mut buf, self.class as u16);
-        buf
+}
+
+impl ToBytes for Question {
+    fn to_bytes(&self, buf: &mut Vec<u8>, names: &mut Compression) {
+        self.name.to_bytes(buf, names);
+        self.kind.to_bytes(buf, names);
+        self.class.to_bytes(buf, names);
     }
 }
 
@@ -281,10 +611,217 @@ impl Display for Question {
 }
 
 impl FromBytes for Question {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Self> {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
         let name = DomainName::from_bytes(buf, cursor)?;
         let kind = Kind::from_bytes(buf, cursor)?;
         let class = Class::from_bytes(buf, cursor)?;
-        Some(Question { name, kind, class })
+        Ok(Question { name, kind, class })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edns::{ClientSubnet, EdnsOption};
+    use crate::record::{Content, Mx, Soa, Srv};
+    use std::mem::discriminant;
+
+    fn record(name: &str, kind: Kind, data: Content) -> Record {
+        Record {
+            name: name.parse().unwrap(),
+            kind,
+            class: Class::Internet,
+            ttl: 3600,
+            data,
+        }
+    }
+
+    fn name(text: &str) -> DomainName {
+        text.parse().unwrap()
+    }
+
+    /// a response using every kind of record data this crate models
+    fn response() -> Packet {
+        let mut flags = Flags::new().with_recusion();
+        flags.set_response(true);
+        flags.set_authoritative(true);
+        flags.set_recursion_available(true);
+        flags.set_authentic_data(true);
+        let edns = Edns::new()
+            .with_payload_size(4096)
+            .with_dnssec_ok()
+            .with_option(EdnsOption::Nsid(b"ns1".to_vec()))
+            .with_option(EdnsOption::ClientSubnet(ClientSubnet::new(
+                "192.0.2.0".parse().unwrap(),
+                24,
+            )))
+            .with_option(EdnsOption::Cookie {
+                client: [1, 2, 3, 4, 5, 6, 7, 8],
+                server: vec![9; 8],
+            })
+            .with_option(EdnsOption::ExtendedError {
+                info_code: 18,
+                extra_text: "prohibited".to_string(),
+            })
+            .with_option(EdnsOption::Unknown {
+                code: 65001,
+                data: vec![1, 2, 3],
+            })
+            .with_option(EdnsOption::Padding(12));
+        let mut packet = Packet::new()
+            .with_id(0x1234)
+            .with_flags(flags)
+            .with_question(Question::build("www.example.com.", Kind::A).unwrap())
+            .with_edns(edns);
+        packet.answers = vec![
+            record(
+                "www.example.com.",
+                Kind::CNAME,
+                Content::DomainName(name("web.example.com.")),
+            ),
+            record(
+                "web.example.com.",
+                Kind::A,
+                Content::IPv4("192.0.2.1".parse().unwrap()),
+            ),
+            record(
+                "web.example.com.",
+                Kind::AAAA,
+                Content::IPv6("2001:db8::1".parse().unwrap()),
+            ),
+            record(
+                "example.com.",
+                Kind::MX,
+                Content::Mx(Mx {
+                    preference: 10,
+                    exchange: name("mail.example.com."),
+                }),
+            ),
+            record(
+                "_sip._udp.example.com.",
+                Kind::SRV,
+                Content::Srv(Srv {
+                    priority: 1,
+                    weight: 5,
+                    port: 5060,
+                    target: name("sip.example.com."),
+                }),
+            ),
+            record(
+                "example.com.",
+                Kind::TXT,
+                Content::Text(vec![b"v=spf1 -all".to_vec(), vec![0, 255]]),
+            ),
+            record(
+                "example.com.",
+                Kind::Unknown(731),
+                Content::Other(vec![0xab, 0xcd]),
+            ),
+        ];
+        packet.authorities = vec![
+            record(
+                "example.com.",
+                Kind::SOA,
+                Content::Soa(Soa {
+                    mname: name("ns1.example.com."),
+                    rname: name("hostmaster.example.com."),
+                    serial: 2024010101,
+                    refresh: 7200,
+                    retry: 900,
+                    expire: 1_209_600,
+                    minimum: 300,
+                }),
+            ),
+            record(
+                "example.com.",
+                Kind::NS,
+                Content::DomainName(name("ns1.example.com.")),
+            ),
+        ];
+        packet.additionals = vec![record(
+            "ns1.example.com.",
+            Kind::A,
+            Content::IPv4("192.0.2.53".parse().unwrap()),
+        )];
+        packet
+    }
+
+    #[test]
+    fn round_trip() {
+        let packet = response();
+        let bytes = packet.to_bytes();
+        let decoded = Packet::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        assert_eq!(decoded.id, packet.id);
+        assert_eq!(decoded.flags.bits(), packet.flags.bits());
+        assert_eq!(decoded.edns, packet.edns);
+        assert_eq!(decoded.to_string(), packet.to_string());
+        let sections = |p: &Packet| [&p.answers, &p.authorities, &p.additionals].map(Vec::len);
+        assert_eq!(sections(&decoded), sections(&packet));
+        let records = |p: &Packet| -> Vec<Record> {
+            [&p.answers, &p.authorities, &p.additionals]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect()
+        };
+        for (a, b) in records(&decoded).iter().zip(records(&packet).iter()) {
+            assert_eq!(discriminant(&a.data), discriminant(&b.data), "{b}");
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_unusual_options() {
+        let unknown = |code: u16, data: &[u8]| EdnsOption::Unknown {
+            code,
+            data: data.to_vec(),
+        };
+        let options = [
+            // extra text that is not utf-8
+            unknown(15, &[0, 18, 0xff, 0xfe]),
+            // padding that is not zero
+            unknown(12, &[0, 1, 0]),
+            // a /24 with a fourth octet, and a /20 with host bits set
+            unknown(8, &[0, 1, 24, 0, 192, 0, 2, 1]),
+            unknown(8, &[0, 1, 20, 0, 192, 0, 0x21]),
+        ];
+        let mut edns = Edns::new();
+        edns.reserved = 0x0101;
+        let edns = options.into_iter().fold(edns, Edns::with_option);
+        let packet = Packet::new().with_edns(edns);
+        let bytes = packet.to_bytes();
+        let decoded = Packet::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.edns, packet.edns);
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn rejects_a_second_opt() {
+        let mut packet = Packet::new().with_edns(Edns::new());
+        packet.additionals.push(Edns::new().to_record());
+        // the header, then the first OPT of eleven octets
+        let error = ParseError::DuplicateOpt { offset: 12 + 11 };
+        assert_eq!(Packet::from_bytes(&packet.to_bytes()).unwrap_err(), error);
+    }
+
+    #[test]
+    fn rejects_opt_not_owned_by_root() {
+        let mut opt = Edns::new().to_record();
+        opt.name = name("example.");
+        let mut packet = Packet::new();
+        packet.additionals.push(opt);
+        let error = ParseError::OptOwner { offset: 12 };
+        assert_eq!(Packet::from_bytes(&packet.to_bytes()).unwrap_err(), error);
+    }
+
+    #[test]
+    fn round_trip_compresses() {
+        let bytes = response().to_bytes();
+        // the first answer's owner repeats the question, at offset 12
+        let answer = 12 + name("www.example.com.").wire_len() + 4;
+        assert_eq!(bytes[answer..answer + 2], [0xc0, 12]);
+        let decoded = Packet::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.answers[0].name, name("www.example.com."));
     }
 }
@@ -1,11 +1,14 @@
 use std::{
     fmt::Display,
     net::{Ipv4Addr, Ipv6Addr},
+    str::FromStr,
 };
 
 use crate::{
-    deserialization::{pop_collection, pop_u16, FromBytes},
+    deserialization::{pop_character_string, pop_collection, pop_u16, FromBytes, ParseError},
     domain_name::DomainName,
+    edns::EdnsOption,
+    serialization::{push_character_string, push_collection, push_u16, Compression, ToBytes},
 };
 
 #[derive(Debug, Clone)]
@@ -17,6 +20,25 @@ pub struct Record {
     pub data: Content,
 }
 
+impl ToBytes for Record {
+    fn to_bytes(&self, buf: &mut Vec<u8>, names: &mut Compression) {
+        self.name.to_bytes(buf, names);
+        self.kind.to_bytes(buf, names);
+        self.class.to_bytes(buf, names);
+        self.ttl.to_bytes(buf, names);
+        // RDLENGTH is patched in once the data has been written
+        let length_at = buf.len();
+        push_u16(buf, 0);
+        if self.kind.is_compressible() {
+            self.data.to_bytes(buf, names);
+        } else {
+            self.data.to_bytes(buf, &mut Compression::disabled());
+        }
+        let length = (buf.len() - length_at - 2) as u16;
+        buf[length_at..length_at + 2].copy_from_slice(&length.to_be_bytes());
+    }
+}
+
 impl Display for Record {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         <DomainName as Display>::fmt(&self.name, f)?;
@@ -30,15 +52,19 @@ impl Display for Record {
 }
 
 impl FromBytes for Record {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Self> {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
         let name = DomainName::from_bytes(buf, cursor)?;
         let kind = Kind::from_bytes(buf, cursor)?;
         let class = Class::from_bytes(buf, cursor)?;
         let ttl = i32::from_bytes(buf, cursor)?;
         let count = pop_u16(buf, cursor)?;
-        let expected = *cursor + count as usize;
+        let start = *cursor;
+        let expected = start + count as usize;
         use Kind::*;
         let data = match kind {
+            // updates use empty data with class NONE or ANY for any type. For
+            // TXT it reads as no strings, which writes back the same
+            _ if count == 0 && kind != TXT => Content::Other(vec![]),
             A => {
                 if count == 4 {
                     let ip = <Ipv4Addr as FromBytes>::from_bytes(buf, cursor)?;
@@ -53,42 +79,68 @@ impl FromBytes for Record {
             }
             AAAA => {
                 let ip = <Ipv6Addr as FromBytes>::from_bytes(buf, cursor)?;
-                    Content::IPv6(ip)
+                Content::IPv6(ip)
             }
             NS => {
                 let domain = <DomainName as FromBytes>::from_bytes(buf, cursor)?;
                 Content::DomainName(domain)
-            },
+            }
             // MD => todo!(),
             // MF => todo!(),
-            CNAME => {
+            CNAME | DNAME => {
                 let domain = <DomainName as FromBytes>::from_bytes(buf, cursor)?;
                 Content::DomainName(domain)
             }
             SOA => {
-                let domain = <DomainName as FromBytes>::from_bytes(buf, cursor)?;
-                Content::DomainName(domain)
+                let soa = <Soa as FromBytes>::from_bytes(buf, cursor)?;
+                Content::Soa(soa)
             }
             // MB => todo!(),
             // MG => todo!(),
             // MR => todo!(),
             // NULL => todo!(),
             // WKS => todo!(),
-            // PTR => todo!(),
+            PTR => {
+                let domain = <DomainName as FromBytes>::from_bytes(buf, cursor)?;
+                Content::DomainName(domain)
+            }
             // HINFO => todo!(),
             // MINFO => todo!(),
-            // MX => todo!(),
+            MX => {
+                let mx = <Mx as FromBytes>::from_bytes(buf, cursor)?;
+                Content::Mx(mx)
+            }
+            SRV => {
+                let srv = <Srv as FromBytes>::from_bytes(buf, cursor)?;
+                Content::Srv(srv)
+            }
+            OPT => {
+                let mut options = Vec::new();
+                while *cursor < expected {
+                    options.push(EdnsOption::from_bytes(buf, cursor)?);
+                }
+                Content::Opt(options)
+            }
             TXT => {
-                let text = pop_collection::<char>(buf, cursor, count as usize)?.iter().collect();
-                Content::Text(text)
-            },
+                let mut strings = Vec::new();
+                while *cursor < expected {
+                    strings.push(pop_character_string(buf, cursor)?);
+                }
+                Content::Text(strings)
+            }
             _ => {
                 let data = pop_collection(buf, cursor, count as usize)?;
                 Content::Other(data)
             }
         };
-        assert!(expected == *cursor, "Cursor was not moved as expected: {} vs {}", expected, *cursor);
-        Some(Record {
+        if expected != *cursor {
+            return Err(ParseError::RdLengthMismatch {
+                offset: start,
+                expected: count as usize,
+                actual: *cursor - start,
+            });
+        }
+        Ok(Record {
             name,
             kind,
             class,
@@ -103,20 +155,92 @@ pub enum Content {
     IPv4(Ipv4Addr),
     IPv6(Ipv6Addr),
     DomainName(DomainName),
-    Text(String),
+    Soa(Soa),
+    Mx(Mx),
+    Srv(Srv),
+    /// the options of an EDNS pseudo-record, see `Edns`
+    Opt(Vec<EdnsOption>),
+    /// one or more character-strings, which need not be valid utf-8. One
+    /// over 255 octets is written as several in a row, and so reads back
+    /// split at each 255 octets, as the wire cannot mark where it ended
+    Text(Vec<Vec<u8>>),
+    /// data of a type this crate does not interpret, kept as is
     Other(Vec<u8>),
 }
 
+impl Content {
+    /// parses data in the generic `\# length hex` form from RFC 3597 5
+    pub fn from_generic(text: &str) -> Option<Content> {
+        let mut parts = text.split_whitespace();
+        if parts.next()? != "\\#" {
+            return None;
+        }
+        let length: usize = parts.next()?.parse().ok()?;
+        let hex: String = parts.collect();
+        if hex.len() != length * 2 || !hex.is_ascii() {
+            return None;
+        }
+        let bytes = (0..length)
+            .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        Some(Content::Other(bytes))
+    }
+}
+
+impl ToBytes for Content {
+    fn to_bytes(&self, buf: &mut Vec<u8>, names: &mut Compression) {
+        match self {
+            Content::IPv4(ip) => ip.to_bytes(buf, names),
+            Content::IPv6(ip) => ip.to_bytes(buf, names),
+            Content::DomainName(dn) => dn.to_bytes(buf, names),
+            Content::Soa(soa) => soa.to_bytes(buf, names),
+            Content::Mx(mx) => mx.to_bytes(buf, names),
+            Content::Srv(srv) => srv.to_bytes(buf, names),
+            Content::Opt(options) => push_collection(buf, options, names),
+            Content::Text(strings) => {
+                for string in strings.iter() {
+                    push_character_string(buf, string);
+                }
+            }
+            Content::Other(bytes) => push_collection(buf, bytes, names),
+        }
+    }
+}
+
 impl Display for Content {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Content::IPv4(ip) => write!(f, "{ip}"),
             Content::IPv6(ip) => write!(f, "{ip}"),
             Content::DomainName(dn) => write!(f, "{dn}"),
-            Content::Text(text) => write!(f, "{text}"),
+            Content::Soa(soa) => write!(f, "{soa}"),
+            Content::Mx(mx) => write!(f, "{mx}"),
+            Content::Srv(srv) => write!(f, "{srv}"),
+            Content::Opt(options) => {
+                for (index, option) in options.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{option}")?;
+                }
+                Ok(())
+            }
+            Content::Text(strings) => {
+                for (index, string) in strings.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " ")?;
+                    }
+                    write_character_string(f, string)?;
+                }
+                Ok(())
+            }
             Content::Other(bytes) => {
+                write!(f, "\\# {}", bytes.len())?;
+                if !bytes.is_empty() {
+                    write!(f, " ")?;
+                }
                 for byte in bytes.iter() {
-                    write!(f, "{byte:02x} ")?;
+                    write!(f, "{byte:02x}")?;
                 }
                 Ok(())
             }
@@ -124,75 +248,283 @@ impl Display for Content {
     }
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+/// writes a character-string in quotes, escaping quotes and backslashes with
+/// a backslash and unprintable bytes as \DDD, RFC 1035 5.1
+fn write_character_string(f: &mut std::fmt::Formatter<'_>, string: &[u8]) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for byte in string.iter() {
+        match byte {
+            b'"' | b'\\' => write!(f, "\\{}", *byte as char)?,
+            0x20..=0x7e => write!(f, "{}", *byte as char)?,
+            _ => write!(f, "\\{byte:03}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// The start of a zone of authority, RFC 1035 3.3.13
+#[derive(Debug, Clone, Default)]
+pub struct Soa {
+    /// the primary nameserver for the zone
+    pub mname: DomainName,
+    /// the mailbox of the person responsible, with the @ as the first dot
+    pub rname: DomainName,
+    /// version of the zone, compared with serial number arithmetic
+    pub serial: u32,
+    /// seconds before secondaries should check for a new serial
+    pub refresh: u32,
+    /// seconds before secondaries should retry a failed refresh
+    pub retry: u32,
+    /// seconds until secondaries stop answering for the zone
+    pub expire: u32,
+    /// the ttl of negative answers, see RFC 2308
+    pub minimum: u32,
+}
+
+impl FromBytes for Soa {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        let mname = DomainName::from_bytes(buf, cursor)?;
+        let rname = DomainName::from_bytes(buf, cursor)?;
+        let serial = u32::from_bytes(buf, cursor)?;
+        let refresh = u32::from_bytes(buf, cursor)?;
+        let retry = u32::from_bytes(buf, cursor)?;
+        let expire = u32::from_bytes(buf, cursor)?;
+        let minimum = u32::from_bytes(buf, cursor)?;
+        Ok(Soa {
+            mname,
+            rname,
+            serial,
+            refresh,
+            retry,
+            expire,
+            minimum,
+        })
+    }
+}
+
+impl ToBytes for Soa {
+    fn to_bytes(&self, buf: &mut Vec<u8>, names: &mut Compression) {
+        self.mname.to_bytes(buf, names);
+        self.rname.to_bytes(buf, names);
+        self.serial.to_bytes(buf, names);
+        self.refresh.to_bytes(buf, names);
+        self.retry.to_bytes(buf, names);
+        self.expire.to_bytes(buf, names);
+        self.minimum.to_bytes(buf, names);
+    }
+}
+
+impl Display for Soa {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {}",
+            self.mname,
+            self.rname,
+            self.serial,
+            self.refresh,
+            self.retry,
+            self.expire,
+            self.minimum
+        )
+    }
+}
+
+/// A mail exchange for the owner name, RFC 1035 3.3.9
+#[derive(Debug, Clone, Default)]
+pub struct Mx {
+    /// lower values are preferred
+    pub preference: u16,
+    pub exchange: DomainName,
+}
+
+impl FromBytes for Mx {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        let preference = pop_u16(buf, cursor)?;
+        let exchange = DomainName::from_bytes(buf, cursor)?;
+        Ok(Mx {
+            preference,
+            exchange,
+        })
+    }
+}
+
+impl ToBytes for Mx {
+    fn to_bytes(&self, buf: &mut Vec<u8>, names: &mut Compression) {
+        push_u16(buf, self.preference);
+        self.exchange.to_bytes(buf, names);
+    }
+}
+
+impl Display for Mx {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.preference, self.exchange)
+    }
+}
+
+/// The location of a service, RFC 2782
+#[derive(Debug, Clone, Default)]
+pub struct Srv {
+    /// targets with lower values must be tried first
+    pub priority: u16,
+    /// relative chance of picking among targets with the same priority
+    pub weight: u16,
+    pub port: u16,
+    /// the host providing the service, the root if it is unavailable
+    pub target: DomainName,
+}
+
+impl FromBytes for Srv {
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        let priority = pop_u16(buf, cursor)?;
+        let weight = pop_u16(buf, cursor)?;
+        let port = pop_u16(buf, cursor)?;
+        let target = DomainName::from_bytes(buf, cursor)?;
+        Ok(Srv {
+            priority,
+            weight,
+            port,
+            target,
+        })
+    }
+}
+
+impl ToBytes for Srv {
+    fn to_bytes(&self, buf: &mut Vec<u8>, names: &mut Compression) {
+        push_u16(buf, self.priority);
+        push_u16(buf, self.weight);
+        push_u16(buf, self.port);
+        self.target.to_bytes(buf, names);
+    }
+}
+
+impl Display for Srv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.priority, self.weight, self.port, self.target
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
 pub enum Kind {
     /// illegal?
-    Undefined = 0,
+    Undefined,
     /// a host address
     #[default]
-    A = 1,
+    A,
     /// an ipv6 address
-    AAAA = 28,
+    AAAA,
+    /// the location of a service, RFC 2782
+    SRV,
     /// an authoritative name server
-    NS = 2,
+    NS,
     /// a mail destination (Obsolete - use MX)     
-    MD = 3,
+    MD,
     /// a mail forwarder (Obsolete - use MX)
-    MF = 4,
+    MF,
     /// the canonical name for an alias
-    CNAME = 5,
+    CNAME,
     /// marks the start of a zone of authority
-    SOA = 6,
+    SOA,
     /// a mailbox domain name (EXPERIMENTAL)
-    MB = 7,
+    MB,
     /// a mail group member (EXPERIMENTAL)
-    MG = 8,
+    MG,
     /// a mail rename domain name (EXPERIMENTAL)
-    MR = 9,
+    MR,
     /// a null RR (EXPERIMENTAL)
-    NULL = 10,
+    NULL,
     /// a well known service description
-    WKS = 11,
+    WKS,
     /// a domain name pointer
-    PTR = 12,
+    PTR,
     /// host information
-    HINFO = 13,
+    HINFO,
     /// mailbox or mail list information
-    MINFO = 14,
+    MINFO,
     /// mail exchange
-    MX = 15,
+    MX,
     /// text strings
-    TXT = 16,
+    TXT,
+    /// redirection of a whole subtree, RFC 6672
+    DNAME,
+    /// the EDNS pseudo-record, RFC 6891
+    OPT,
+    /// any type this crate does not know about, RFC 3597
+    Unknown(u16),
 }
 
-impl TryFrom<u16> for Kind {
-    type Error = ();
+impl Kind {
+    /// only the types defined in RFC 1035 may use pointers in their data,
+    /// see RFC 3597 section 4
+    pub fn is_compressible(&self) -> bool {
+        use Kind::*;
+        matches!(
+            self,
+            NS | MD | MF | CNAME | SOA | MB | MG | MR | PTR | MINFO | MX
+        )
+    }
+}
 
-    fn try_from(value: u16) -> Result<Self, Self::Error> {
+impl From<u16> for Kind {
+    fn from(value: u16) -> Self {
         use Kind::*;
         match value {
-            0 => Ok(Undefined),
-            1 => Ok(A),
-            2 => Ok(NS),
-            3 => Ok(MD),
-            4 => Ok(MF),
-            5 => Ok(CNAME),
-            6 => Ok(SOA),
-            7 => Ok(MB),
-            8 => Ok(MG),
-            9 => Ok(MR),
-            10 => Ok(NULL),
-            11 => Ok(WKS),
-            12 => Ok(PTR),
-            13 => Ok(HINFO),
-            14 => Ok(MINFO),
-            15 => Ok(MX),
-            16 => Ok(TXT),
-            28 => Ok(AAAA),
-            _ => {
-                println!("failed to make Kind from {}", value);
-                Err(())
-            },
+            0 => Undefined,
+            1 => A,
+            2 => NS,
+            3 => MD,
+            4 => MF,
+            5 => CNAME,
+            6 => SOA,
+            7 => MB,
+            8 => MG,
+            9 => MR,
+            10 => NULL,
+            11 => WKS,
+            12 => PTR,
+            13 => HINFO,
+            14 => MINFO,
+            15 => MX,
+            16 => TXT,
+            28 => AAAA,
+            33 => SRV,
+            39 => DNAME,
+            41 => OPT,
+            n => Unknown(n),
+        }
+    }
+}
+
+impl From<Kind> for u16 {
+    fn from(value: Kind) -> Self {
+        use Kind::*;
+        match value {
+            Undefined => 0,
+            A => 1,
+            NS => 2,
+            MD => 3,
+            MF => 4,
+            CNAME => 5,
+            SOA => 6,
+            MB => 7,
+            MG => 8,
+            MR => 9,
+            NULL => 10,
+            WKS => 11,
+            PTR => 12,
+            HINFO => 13,
+            MINFO => 14,
+            MX => 15,
+            TXT => 16,
+            AAAA => 28,
+            SRV => 33,
+            DNAME => 39,
+            OPT => 41,
+            Unknown(n) => n,
         }
     }
 }
@@ -200,9 +532,8 @@ impl TryFrom<u16> for Kind {
 impl Display for Kind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
-            Kind::Undefined => "???",
+            Kind::Undefined => "TYPE0",
             Kind::A => "A",
-            Kind::AAAA => "AAAA",
             Kind::NS => "NS",
             Kind::MD => "MD",
             Kind::MF => "MF",
@@ -218,31 +549,109 @@ impl Display for Kind {
             Kind::MINFO => "MINFO",
             Kind::MX => "MX",
             Kind::TXT => "TXT",
+            Kind::AAAA => "AAAA",
+            Kind::SRV => "SRV",
+            Kind::DNAME => "DNAME",
+            Kind::OPT => "OPT",
+            Kind::Unknown(n) => return write!(f, "TYPE{n}"),
         };
         write!(f, "{s}")
     }
 }
 
+impl FromStr for Kind {
+    type Err = ();
+
+    /// accepts mnemonics in any case, or the generic TYPE1234 form
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Kind::*;
+        let s = s.to_ascii_uppercase();
+        match s.as_str() {
+            "A" => Ok(A),
+            "NS" => Ok(NS),
+            "MD" => Ok(MD),
+            "MF" => Ok(MF),
+            "CNAME" => Ok(CNAME),
+            "SOA" => Ok(SOA),
+            "MB" => Ok(MB),
+            "MG" => Ok(MG),
+            "MR" => Ok(MR),
+            "NULL" => Ok(NULL),
+            "WKS" => Ok(WKS),
+            "PTR" => Ok(PTR),
+            "HINFO" => Ok(HINFO),
+            "MINFO" => Ok(MINFO),
+            "MX" => Ok(MX),
+            "TXT" => Ok(TXT),
+            "AAAA" => Ok(AAAA),
+            "SRV" => Ok(SRV),
+            "DNAME" => Ok(DNAME),
+            "OPT" => Ok(OPT),
+            _ => {
+                let number = s.strip_prefix("TYPE").ok_or(())?;
+                number.parse::<u16>().map(Kind::from).map_err(|_| ())
+            }
+        }
+    }
+}
+
+impl ToBytes for Kind {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        push_u16(buf, u16::from(*self));
+    }
+}
+
 impl FromBytes for Kind {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Kind> {
-        let num = pop_u16(buf, cursor)?;
-        num.try_into().ok()
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Kind, ParseError> {
+        pop_u16(buf, cursor).map(Kind::from)
     }
 }
 
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
 pub enum Class {
+    /// the Internet
     #[default]
-    Internet = 1,
+    Internet,
+    /// the CSNET class (Obsolete)
+    CSNet,
+    /// the CHAOS class
+    Chaos,
+    /// Hesiod
+    Hesiod,
+    /// used by dynamic updates to delete records, RFC 2136
+    None,
+    /// matches any class in questions
+    Any,
+    /// any class this crate does not know about, RFC 3597
+    Unknown(u16),
+}
+
+impl From<u16> for Class {
+    fn from(value: u16) -> Self {
+        use Class::*;
+        match value {
+            1 => Internet,
+            2 => CSNet,
+            3 => Chaos,
+            4 => Hesiod,
+            254 => None,
+            255 => Any,
+            n => Unknown(n),
+        }
+    }
 }
-impl TryFrom<u16> for Class {
-    type Error = ();
 
-    fn try_from(value: u16) -> Result<Self, Self::Error> {
+impl From<Class> for u16 {
+    fn from(value: Class) -> Self {
         use Class::*;
         match value {
-            1 => Ok(Internet),
-            _ => Err(()),
+            Internet => 1,
+            CSNet => 2,
+            Chaos => 3,
+            Hesiod => 4,
+            None => 254,
+            Any => 255,
+            Unknown(n) => n,
         }
     }
 }
@@ -251,14 +660,95 @@ impl Display for Class {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             Class::Internet => "IN",
+            Class::CSNet => "CS",
+            Class::Chaos => "CH",
+            Class::Hesiod => "HS",
+            Class::None => "NONE",
+            Class::Any => "ANY",
+            Class::Unknown(n) => return write!(f, "CLASS{n}"),
         };
         write!(f, "{s}")
     }
 }
 
+impl FromStr for Class {
+    type Err = ();
+
+    /// accepts mnemonics in any case, or the generic CLASS32 form
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_uppercase();
+        match s.as_str() {
+            "IN" => Ok(Class::Internet),
+            "CS" => Ok(Class::CSNet),
+            "CH" => Ok(Class::Chaos),
+            "HS" => Ok(Class::Hesiod),
+            "NONE" => Ok(Class::None),
+            "ANY" => Ok(Class::Any),
+            _ => {
+                let number = s.strip_prefix("CLASS").ok_or(())?;
+                number.parse::<u16>().map(Class::from).map_err(|_| ())
+            }
+        }
+    }
+}
+
+impl ToBytes for Class {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        push_u16(buf, u16::from(*self));
+    }
+}
+
 impl FromBytes for Class {
-    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Option<Class> {
-        let num = pop_u16(buf, cursor)?;
-        num.try_into().ok()
+    fn from_bytes(buf: &[u8], cursor: &mut usize) -> Result<Class, ParseError> {
+        pop_u16(buf, cursor).map(Class::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// encodes text data and reads it back
+    fn round_trip(strings: Vec<Vec<u8>>) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let record = Record {
+            name: DomainName::root(),
+            kind: Kind::TXT,
+            class: Class::Internet,
+            ttl: 0,
+            data: Content::Text(strings),
+        };
+        let mut buf = vec![];
+        record.to_bytes(&mut buf, &mut Compression::new());
+        let decoded = Record::from_bytes(&buf, &mut 0).unwrap();
+        let mut again = vec![];
+        decoded.to_bytes(&mut again, &mut Compression::new());
+        assert_eq!(again, buf);
+        match decoded.data {
+            Content::Text(strings) => (buf, strings),
+            data => panic!("not text: {data:?}"),
+        }
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let strings = vec![b"v=spf1 -all".to_vec(), vec![], vec![0, 255]];
+        assert_eq!(round_trip(strings.clone()).1, strings);
+    }
+
+    #[test]
+    fn no_strings_round_trip_as_empty_data() {
+        let (buf, strings) = round_trip(vec![]);
+        assert!(strings.is_empty());
+        // rdlength is the last field before the data
+        assert_eq!(buf[buf.len() - 2..], [0, 0]);
+    }
+
+    #[test]
+    fn long_strings_split_at_255_octets() {
+        let long = vec![b'x'; 600];
+        let (_, strings) = round_trip(vec![long.clone(), b"end".to_vec()]);
+        let lengths: Vec<usize> = strings.iter().map(Vec::len).collect();
+        assert_eq!(lengths, [255, 255, 90, 3]);
+        assert_eq!(strings[..3].concat(), long);
     }
 }
@@ -0,0 +1,776 @@
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::cache::{Cache, Cached};
+use crate::deserialization::ParseError;
+use crate::domain_name::{DomainName, NameError};
+use crate::edns::{Edns, DEFAULT_PAYLOAD_SIZE};
+use crate::packet::{Flags, Packet, Question, Rcode};
+use crate::record::{Class, Content, Kind, Record, Srv};
+use crate::root_hints::RootHints;
+use crate::transport::{exchange, reachable, Protocol};
+
+/// the port nameservers listen on
+pub(crate) const DNS_PORT: u16 = 53;
+
+/// Bounds on how much work a single lookup may do
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// how many nested lookups of nameserver addresses are allowed
+    pub max_depth: usize,
+    /// how many referrals a single lookup may follow
+    pub max_referrals: usize,
+    /// how many CNAME or DNAME records may be followed for one name
+    pub max_aliases: usize,
+    /// how many queries a single lookup may send, counting those for the
+    /// addresses of nameservers it looks up along the way
+    pub max_queries: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_depth: 8,
+            max_referrals: 16,
+            max_aliases: 8,
+            max_queries: 100,
+        }
+    }
+}
+
+/// How a `Resolver` reaches nameservers
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// where every lookup starts, the root servers unless changed
+    pub servers: Vec<SocketAddr>,
+    /// how long to wait for each response
+    pub timeout: Duration,
+    /// how many more times to ask a server that did not respond
+    pub retries: usize,
+    /// the local addresses queries are sent from, on a random ephemeral
+    /// port, chosen by the family of each server
+    pub source_v4: Ipv4Addr,
+    pub source_v6: Ipv6Addr,
+    /// whether nameservers may be reached over each family at all
+    pub ipv4: bool,
+    pub ipv6: bool,
+    pub protocol: Protocol,
+    /// advertised with EDNS, or None to send plain RFC 1035 queries
+    pub udp_payload_size: Option<u16>,
+    pub limits: Limits,
+    /// how many RRsets to keep between lookups, or zero to not cache
+    pub cache_size: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            servers: RootHints::default().addresses(),
+            timeout: Duration::from_secs(2),
+            retries: 1,
+            source_v4: Ipv4Addr::UNSPECIFIED,
+            source_v6: Ipv6Addr::UNSPECIFIED,
+            ipv4: true,
+            ipv6: true,
+            protocol: Protocol::Udp,
+            udp_payload_size: Some(DEFAULT_PAYLOAD_SIZE),
+            limits: Limits::default(),
+            cache_size: 4096,
+        }
+    }
+}
+
+impl Config {
+    pub fn new() -> Config {
+        Config::default()
+    }
+    pub fn with_servers(mut self, servers: Vec<SocketAddr>) -> Config {
+        self.servers = servers;
+        self
+    }
+    /// starts lookups from every address in the hints
+    pub fn with_root_hints(self, hints: &RootHints) -> Config {
+        self.with_servers(hints.addresses())
+    }
+    pub fn with_timeout(mut self, timeout: Duration) -> Config {
+        self.timeout = timeout;
+        self
+    }
+    pub fn with_retries(mut self, retries: usize) -> Config {
+        self.retries = retries;
+        self
+    }
+    /// sets the source address for servers of the same family
+    pub fn with_source(mut self, source: IpAddr) -> Config {
+        match source {
+            IpAddr::V4(ip) => self.source_v4 = ip,
+            IpAddr::V6(ip) => self.source_v6 = ip,
+        }
+        self
+    }
+    pub fn with_ipv4(mut self, enabled: bool) -> Config {
+        self.ipv4 = enabled;
+        self
+    }
+    pub fn with_ipv6(mut self, enabled: bool) -> Config {
+        self.ipv6 = enabled;
+        self
+    }
+    pub fn with_protocol(mut self, protocol: Protocol) -> Config {
+        self.protocol = protocol;
+        self
+    }
+    pub fn with_udp_payload_size(mut self, size: Option<u16>) -> Config {
+        self.udp_payload_size = size;
+        self
+    }
+    pub fn with_limits(mut self, limits: Limits) -> Config {
+        self.limits = limits;
+        self
+    }
+    pub fn with_cache_size(mut self, size: usize) -> Config {
+        self.cache_size = size;
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum ResolveError {
+    Io(std::io::Error),
+    /// no response arrived within the configured timeout
+    Timeout,
+    /// a tcp response did not match the query it was sent for
+    Mismatch,
+    Parse(ParseError),
+    /// a server answered with an error response code
+    Rcode(Rcode),
+    /// the name does not exist
+    NxDomain(DomainName),
+    /// the name exists but has no records of the kind asked for
+    NoData(DomainName, Kind),
+    /// a response had no answer and no usable referral
+    NoReferral,
+    /// none of the nameservers in a referral could be reached or resolved
+    NoServers,
+    TooManyReferrals,
+    TooDeep,
+    /// the lookup sent as many queries as `Limits` allows
+    TooManyQueries,
+    TooManyAliases,
+    /// a CNAME or DNAME chain led back to a name already seen
+    AliasLoop(DomainName),
+    /// a name given to look up was not valid
+    Name(NameError),
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ResolveError::*;
+        match self {
+            Io(e) => write!(f, "network error: {e}"),
+            Timeout => write!(f, "timed out waiting for a response"),
+            Mismatch => write!(f, "response does not match the query"),
+            Parse(e) => write!(f, "malformed response: {e}"),
+            Rcode(rcode) => write!(f, "server responded with {rcode}"),
+            NxDomain(name) => write!(f, "{name} does not exist"),
+            NoData(name, kind) => write!(f, "{name} has no {kind} records"),
+            NoReferral => write!(f, "response contained neither answer nor referral"),
+            NoServers => write!(f, "no nameserver in the referral could be reached"),
+            TooManyReferrals => write!(f, "too many referrals"),
+            TooDeep => write!(f, "nameserver lookups nested too deeply"),
+            TooManyQueries => write!(f, "too many queries for one lookup"),
+            TooManyAliases => write!(f, "too many aliases"),
+            AliasLoop(name) => write!(f, "aliases loop back to {name}"),
+            Name(e) => write!(f, "invalid name: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+impl From<std::io::Error> for ResolveError {
+    fn from(e: std::io::Error) -> Self {
+        ResolveError::Io(e)
+    }
+}
+
+impl From<ParseError> for ResolveError {
+    fn from(e: ParseError) -> Self {
+        ResolveError::Parse(e)
+    }
+}
+
+impl From<NameError> for ResolveError {
+    fn from(e: NameError) -> Self {
+        ResolveError::Name(e)
+    }
+}
+
+/// The outcome of a successful lookup
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    /// the records of the requested kind, owned by the last name in the chain
+    pub records: Vec<Record>,
+    /// the CNAME and DNAME records followed to get there, in order
+    pub chain: Vec<Record>,
+}
+
+/// A mail server for a domain, with the addresses it can be reached at
+#[derive(Debug, Clone, Default)]
+pub struct MailExchanger {
+    pub preference: u16,
+    pub exchange: DomainName,
+    /// empty if the exchange could not be resolved
+    pub addresses: Vec<IpAddr>,
+}
+
+/// An iterative resolver, reusable across lookups. Clones share one cache
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    config: Config,
+    cache: Arc<Mutex<Cache>>,
+    /// whether this host has a route to the servers of each family
+    ipv4: bool,
+    ipv6: bool,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Resolver::new(Config::default())
+    }
+}
+
+impl Resolver {
+    /// Checks once which address families this host can reach the
+    /// configured servers over, so that an IPv4 or IPv6 only host does not
+    /// wait on servers it has no route to
+    pub fn new(config: Config) -> Resolver {
+        let probe = |enabled: bool, source: IpAddr| {
+            enabled
+                && config
+                    .servers
+                    .iter()
+                    .filter(|server| server.is_ipv4() == source.is_ipv4())
+                    .any(|server| reachable(source, *server))
+        };
+        let ipv4 = probe(config.ipv4, IpAddr::V4(config.source_v4));
+        let ipv6 = probe(config.ipv6, IpAddr::V6(config.source_v6));
+        let cache = Arc::new(Mutex::new(Cache::new(config.cache_size)));
+        Resolver {
+            config,
+            cache,
+            ipv4,
+            ipv6,
+        }
+    }
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+    /// the records kept from earlier lookups
+    pub fn cache(&self) -> MutexGuard<'_, Cache> {
+        // the cache is left consistent even if a lookup panicked
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    /// Sends a single non-recursive question to a server, retrying if it
+    /// does not respond in time
+    pub fn query(&self, server: SocketAddr, question: Question) -> Result<Packet, ResolveError> {
+        let mut query = Packet::new()
+            .with_flags(Flags::new())
+            .with_question(question);
+        if let Some(size) = self.config.udp_payload_size {
+            query = query.with_edns(Edns::new().with_payload_size(size));
+        }
+        let response = self.exchange(server, &query)?;
+        // servers that predate EDNS reject the OPT record, RFC 6891 7
+        let rejected = matches!(response.rcode(), Rcode::FormErr | Rcode::NotImp);
+        if rejected && query.edns.is_some() && response.edns.is_none() {
+            query.edns = None;
+            return self.exchange(server, &query);
+        }
+        Ok(response)
+    }
+    /// sends a query, retrying if the server does not respond in time
+    fn exchange(&self, server: SocketAddr, query: &Packet) -> Result<Packet, ResolveError> {
+        let config = &self.config;
+        let mut attempts = 0;
+        loop {
+            match exchange(
+                config.protocol,
+                query,
+                server,
+                self.source(server),
+                config.timeout,
+            ) {
+                Err(ResolveError::Timeout) if attempts < config.retries => attempts += 1,
+                result => return result,
+            }
+        }
+    }
+    /// Asks the configured servers for the current root servers, RFC 8109,
+    /// and starts later lookups from them instead
+    pub fn prime(&mut self) -> Result<RootHints, ResolveError> {
+        let servers = self.usable(&self.config.servers);
+        let response = self.query_any(
+            &servers,
+            &DomainName::root(),
+            Kind::NS,
+            &mut Lookup::default(),
+        )?;
+        let rcode = response.rcode();
+        if rcode != Rcode::NoError {
+            return Err(ResolveError::Rcode(rcode));
+        }
+        let records: Vec<Record> = response
+            .answers
+            .into_iter()
+            .chain(response.additionals)
+            .collect();
+        let hints = RootHints::from_records(&records);
+        if self.usable(&hints.addresses()).is_empty() {
+            return Err(ResolveError::NoServers);
+        }
+        self.config.servers = hints.addresses();
+        Ok(hints)
+    }
+    /// Resolves a name by walking down from the configured servers,
+    /// following referrals until a server answers authoritatively, and
+    /// following any aliases in the answer to their target. A name that
+    /// does not exist, or has no records of the kind, is reported as
+    /// `NxDomain` or `NoData` rather than as an empty answer
+    pub fn resolve(&self, name: &DomainName, kind: Kind) -> Result<Resolution, ResolveError> {
+        let limits = &self.config.limits;
+        let mut chain = Vec::new();
+        let mut seen = vec![name.clone()];
+        let mut current = name.clone();
+        let mut lookup = Lookup::default();
+        loop {
+            let answers = self.resolve_from_root(&current, kind, &mut lookup, 0)?;
+            let start = current.clone();
+            // the server may have followed some of the chain for us already
+            loop {
+                let records: Vec<Record> = answers
+                    .iter()
+                    .filter(|r| r.kind == kind && r.name == current)
+                    .cloned()
+                    .collect();
+                if !records.is_empty() {
+                    return Ok(Resolution { records, chain });
+                }
+                let Some((alias, target)) = follow_alias(&answers, &current) else {
+                    break;
+                };
+                if chain.len() >= limits.max_aliases {
+                    return Err(ResolveError::TooManyAliases);
+                }
+                if seen.contains(&target) {
+                    return Err(ResolveError::AliasLoop(target));
+                }
+                chain.push(alias.clone());
+                seen.push(target.clone());
+                current = target;
+            }
+            if current == start {
+                return Err(ResolveError::NoData(current, kind));
+            }
+        }
+    }
+    /// Looks up the host names an address maps back to
+    pub fn reverse(&self, ip: IpAddr) -> Result<Vec<DomainName>, ResolveError> {
+        let names = self
+            .records(&DomainName::reverse(ip), Kind::PTR)?
+            .into_iter()
+            .filter_map(|record| match record.data {
+                Content::DomainName(name) => Some(name),
+                _ => None,
+            })
+            .collect();
+        Ok(names)
+    }
+    /// Looks up the mail exchangers for a domain and their addresses, most
+    /// preferred first
+    pub fn mail_exchangers(&self, name: &DomainName) -> Result<Vec<MailExchanger>, ResolveError> {
+        let mut exchangers: Vec<MailExchanger> = self
+            .records(name, Kind::MX)?
+            .iter()
+            .filter_map(|record| match &record.data {
+                Content::Mx(mx) => Some(mx),
+                _ => None,
+            })
+            .map(|mx| MailExchanger {
+                preference: mx.preference,
+                exchange: mx.exchange.clone(),
+                addresses: self.addresses(&mx.exchange),
+            })
+            .collect();
+        exchangers.sort_by_key(|exchanger| exchanger.preference);
+        Ok(exchangers)
+    }
+    /// the records of a kind for a name, where having none is not an error
+    fn records(&self, name: &DomainName, kind: Kind) -> Result<Vec<Record>, ResolveError> {
+        match self.resolve(name, kind) {
+            Ok(resolution) => Ok(resolution.records),
+            Err(ResolveError::NoData(..)) => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+    /// the addresses of a host, empty if it could not be resolved
+    fn addresses(&self, name: &DomainName) -> Vec<IpAddr> {
+        // a null MX, RFC 7505, has no host to look up
+        if name.is_root() {
+            return vec![];
+        }
+        [Kind::A, Kind::AAAA]
+            .into_iter()
+            .filter_map(|kind| self.resolve(name, kind).ok())
+            .flat_map(|resolution| resolution.records)
+            .filter_map(|record| address(&record))
+            .collect()
+    }
+    /// the local address to send queries to a server from
+    fn source(&self, server: SocketAddr) -> IpAddr {
+        match server {
+            SocketAddr::V4(_) => IpAddr::V4(self.config.source_v4),
+            SocketAddr::V6(_) => IpAddr::V6(self.config.source_v6),
+        }
+    }
+    /// the servers reachable over the families this host can use
+    fn usable(&self, servers: &[SocketAddr]) -> Vec<SocketAddr> {
+        servers
+            .iter()
+            .filter(|server| match server {
+                SocketAddr::V4(_) => self.ipv4,
+                SocketAddr::V6(_) => self.ipv6,
+            })
+            .copied()
+            .collect()
+    }
+    /// the kinds of address record worth looking up for a nameserver
+    fn address_kinds(&self) -> Vec<Kind> {
+        let mut kinds = vec![];
+        if self.ipv4 {
+            kinds.push(Kind::A);
+        }
+        if self.ipv6 {
+            kinds.push(Kind::AAAA);
+        }
+        kinds
+    }
+    /// Looks up `_service._protocol.name` and returns the addresses of the
+    /// servers providing it, in the order they should be tried
+    pub fn services(
+        &self,
+        service: &str,
+        protocol: &str,
+        name: &DomainName,
+    ) -> Result<Vec<SocketAddr>, ResolveError> {
+        let service = service.trim_start_matches('_');
+        let protocol = protocol.trim_start_matches('_');
+        let owner = format!("_{service}._{protocol}")
+            .parse::<DomainName>()?
+            .append(name)?;
+        let targets: Vec<Srv> = self
+            .records(&owner, Kind::SRV)?
+            .into_iter()
+            .filter_map(|record| match record.data {
+                Content::Srv(srv) => Some(srv),
+                _ => None,
+            })
+            .collect();
+        // a single target of "." means the service is decidedly not available
+        if let [srv] = targets.as_slice() {
+            if srv.target.is_root() {
+                return Ok(vec![]);
+            }
+        }
+        let addresses = order_services(targets)
+            .into_iter()
+            .flat_map(|srv| {
+                self.addresses(&srv.target)
+                    .into_iter()
+                    .map(move |ip| SocketAddr::new(ip, srv.port))
+            })
+            .collect();
+        Ok(addresses)
+    }
+    fn resolve_from_root(
+        &self,
+        name: &DomainName,
+        kind: Kind,
+        lookup: &mut Lookup,
+        depth: usize,
+    ) -> Result<Vec<Record>, ResolveError> {
+        let limits = &self.config.limits;
+        if depth > limits.max_depth {
+            return Err(ResolveError::TooDeep);
+        }
+        match self.cache().lookup(name, kind, Class::Internet) {
+            Some(Cached::Records(records)) => return Ok(records),
+            Some(Cached::NoData { .. }) => return Err(ResolveError::NoData(name.clone(), kind)),
+            Some(Cached::NxDomain { .. }) => return Err(ResolveError::NxDomain(name.clone())),
+            Some(Cached::Delegation { .. }) | None => {}
+        }
+        if let Some(records) = self.cache().get(name, Kind::CNAME, Class::Internet) {
+            return Ok(records);
+        }
+        // start from the closest zone whose servers are known, keeping track
+        // of the zone being asked so its servers can only speak for it
+        let (mut zone, delegation) = self.cache().delegation(name).unwrap_or_default();
+        let mut servers = self.usable(
+            &delegation
+                .iter()
+                .filter_map(server_address)
+                .collect::<Vec<_>>(),
+        );
+        if servers.is_empty() {
+            zone = DomainName::root();
+            servers = self.usable(&self.config.servers);
+        }
+        for _ in 0..limits.max_referrals {
+            let response = self.query_any(&servers, name, kind, lookup)?;
+            let rcode = response.rcode();
+            let soa = negative_soa(&response, name, &zone);
+            // a server only speaks for its own zone, so where an alias chain
+            // leaves it the rest is looked up afresh
+            let answers: Vec<Record> = response
+                .answers
+                .iter()
+                .filter(|record| record.name.is_subdomain_of(&zone))
+                .cloned()
+                .collect();
+            match rcode {
+                Rcode::NoError => {}
+                // with an alias in the answer, it is the target that is missing
+                Rcode::NXDomain if !answers.is_empty() => {}
+                Rcode::NXDomain => {
+                    if let Some(soa) = soa {
+                        self.cache().insert_nxdomain(name, Class::Internet, soa);
+                    }
+                    return Err(ResolveError::NxDomain(name.clone()));
+                }
+                _ => return Err(ResolveError::Rcode(rcode)),
+            }
+            // an SOA instead of a referral also means no data, RFC 2308 2.2
+            if answers.is_empty() && (response.flags.is_authoritative() || soa.is_some()) {
+                if let Some(soa) = soa {
+                    self.cache().insert_nodata(name, kind, Class::Internet, soa);
+                }
+                return Err(ResolveError::NoData(name.clone(), kind));
+            }
+            if !answers.is_empty() {
+                // only the records for the name asked about are cached
+                let owned: Vec<Record> = answers
+                    .iter()
+                    .filter(|record| record.name == *name)
+                    .cloned()
+                    .collect();
+                self.cache().insert(&owned);
+                return Ok(answers);
+            }
+            (zone, servers) = self.follow_referral(&response, name, &zone, lookup, depth)?;
+        }
+        Err(ResolveError::TooManyReferrals)
+    }
+    /// asks each server in turn until one of them responds
+    fn query_any(
+        &self,
+        servers: &[SocketAddr],
+        name: &DomainName,
+        kind: Kind,
+        lookup: &mut Lookup,
+    ) -> Result<Packet, ResolveError> {
+        let mut error = ResolveError::NoServers;
+        for server in servers.iter() {
+            if lookup.queries >= self.config.limits.max_queries {
+                return Err(ResolveError::TooManyQueries);
+            }
+            lookup.queries += 1;
+            let question = Question::new().with_name(name.clone()).with_kind(kind);
+            match self.query(*server, question) {
+                Ok(response) => return Ok(response),
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+    /// Finds the zone a response from a server for `zone` delegates to and
+    /// the addresses of its nameservers, preferring glue and looking up the
+    /// names of any servers without it. A server may only delegate zones
+    /// strictly below its own, and only give glue for names inside its own
+    fn follow_referral(
+        &self,
+        response: &Packet,
+        name: &DomainName,
+        zone: &DomainName,
+        lookup: &mut Lookup,
+        depth: usize,
+    ) -> Result<(DomainName, Vec<SocketAddr>), ResolveError> {
+        let child = response
+            .authorities
+            .iter()
+            .filter(|record| record.kind == Kind::NS && name.is_subdomain_of(&record.name))
+            .filter(|record| record.name != *zone && record.name.is_subdomain_of(zone))
+            .map(|record| &record.name)
+            .max_by_key(|owner| owner.labels().len())
+            .ok_or(ResolveError::NoReferral)?
+            .clone();
+        let delegation: Vec<Record> = response
+            .authorities
+            .iter()
+            .filter(|record| record.kind == Kind::NS && record.name == child)
+            .cloned()
+            .collect();
+        let nameservers: Vec<&DomainName> = delegation
+            .iter()
+            .filter_map(|record| match &record.data {
+                Content::DomainName(ns) => Some(ns),
+                _ => None,
+            })
+            .collect();
+        if nameservers.is_empty() {
+            return Err(ResolveError::NoReferral);
+        }
+        let glue: Vec<Record> = response
+            .additionals
+            .iter()
+            .filter(|record| nameservers.contains(&&record.name))
+            .filter(|record| matches!(record.kind, Kind::A | Kind::AAAA))
+            .filter(|record| record.name.is_subdomain_of(zone))
+            .cloned()
+            .collect();
+        self.cache()
+            .insert_delegation(&child, Class::Internet, &delegation, &glue);
+        let glue: Vec<SocketAddr> = glue.iter().filter_map(server_address).collect();
+        let glue = self.usable(&glue);
+        if !glue.is_empty() {
+            return Ok((child, glue));
+        }
+        for ns in nameservers {
+            // without glue, the servers of a zone can only be found through
+            // the zone itself, and a server already being looked up would
+            // lead back here
+            if ns.is_subdomain_of(&child) || lookup.pending.contains(ns) {
+                continue;
+            }
+            lookup.pending.push(ns.clone());
+            let addresses = self.nameserver_addresses(ns, lookup, depth);
+            lookup.pending.pop();
+            let addresses = addresses?;
+            if !addresses.is_empty() {
+                return Ok((child, addresses));
+            }
+        }
+        Err(ResolveError::NoServers)
+    }
+    /// the addresses of a nameserver, empty if it could not be resolved.
+    /// Only running out of queries is an error, as no other server could
+    /// be tried either
+    fn nameserver_addresses(
+        &self,
+        ns: &DomainName,
+        lookup: &mut Lookup,
+        depth: usize,
+    ) -> Result<Vec<SocketAddr>, ResolveError> {
+        for kind in self.address_kinds() {
+            match self.resolve_from_root(ns, kind, lookup, depth + 1) {
+                Ok(records) => {
+                    let addresses: Vec<SocketAddr> =
+                        records.iter().filter_map(server_address).collect();
+                    if !addresses.is_empty() {
+                        return Ok(addresses);
+                    }
+                }
+                Err(ResolveError::TooManyQueries) => return Err(ResolveError::TooManyQueries),
+                Err(_) => {}
+            }
+        }
+        Ok(vec![])
+    }
+}
+
+/// What a single lookup has used so far, shared with the lookups of
+/// nameserver addresses it makes along the way
+#[derive(Debug, Default)]
+struct Lookup {
+    queries: usize,
+    /// the nameservers whose addresses are being looked up
+    pending: Vec<DomainName>,
+}
+
+/// sorts targets by priority, then within each priority shuffles them so
+/// that each is picked first in proportion to its weight, RFC 2782
+fn order_services(mut targets: Vec<Srv>) -> Vec<Srv> {
+    let mut rng = rand::thread_rng();
+    targets.sort_by_key(|srv| (srv.priority, srv.weight != 0));
+    let mut ordered = Vec::with_capacity(targets.len());
+    while !targets.is_empty() {
+        let priority = targets[0].priority;
+        let count = targets
+            .iter()
+            .take_while(|srv| srv.priority == priority)
+            .count();
+        let mut group: Vec<Srv> = targets.drain(..count).collect();
+        while !group.is_empty() {
+            let total: u32 = group.iter().map(|srv| srv.weight as u32).sum();
+            let pick = rng.gen_range(0..=total);
+            let mut running = 0;
+            let index = group
+                .iter()
+                .position(|srv| {
+                    running += srv.weight as u32;
+                    running >= pick
+                })
+                .unwrap_or(0);
+            ordered.push(group.remove(index));
+        }
+    }
+    ordered
+}
+
+/// the address held by an address record
+fn address(record: &Record) -> Option<IpAddr> {
+    match record.data {
+        Content::IPv4(ip) => Some(IpAddr::V4(ip)),
+        Content::IPv6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    }
+}
+
+/// the address to reach a nameserver at, from one of its address records
+fn server_address(record: &Record) -> Option<SocketAddr> {
+    address(record).map(|ip| SocketAddr::new(ip, DNS_PORT))
+}
+
+/// the SOA a negative response carries for the zone holding the name, whose
+/// ttl bounds how long the answer may be cached. Only an SOA within the zone
+/// of the server that sent it is believed
+fn negative_soa<'a>(
+    response: &'a Packet,
+    name: &DomainName,
+    zone: &DomainName,
+) -> Option<&'a Record> {
+    response.authorities.iter().find(|record| {
+        record.kind == Kind::SOA
+            && name.is_subdomain_of(&record.name)
+            && record.name.is_subdomain_of(zone)
+    })
+}
+
+/// finds a CNAME for the name, or a DNAME for one of its ancestors, and
+/// the name it redirects to
+fn follow_alias<'a>(answers: &'a [Record], name: &DomainName) -> Option<(&'a Record, DomainName)> {
+    answers
+        .iter()
+        .find_map(|record| match (&record.kind, &record.data) {
+            (Kind::CNAME, Content::DomainName(target)) if record.name == *name => {
+                Some((record, target.clone()))
+            }
+            (Kind::DNAME, Content::DomainName(target)) => name
+                .replace_suffix(&record.name, target)
+                .map(|synthesized| (record, synthesized)),
+            _ => None,
+        })
+}
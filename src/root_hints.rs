@@ -0,0 +1,140 @@
+use std::fmt::Display;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+
+use crate::domain_name::DomainName;
+use crate::record::{Content, Kind, Record};
+use crate::resolver::DNS_PORT;
+use crate::zone::{ZoneError, ZoneReader};
+use crate::ROOT_SERVERS;
+
+/// The servers a resolver starts from, as listed in IANA's `named.root`
+#[derive(Debug, Clone)]
+pub struct RootHints {
+    pub servers: Vec<RootServer>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RootServer {
+    pub name: DomainName,
+    pub addresses: Vec<IpAddr>,
+}
+
+/// the hints compiled into this crate
+impl Default for RootHints {
+    fn default() -> Self {
+        let servers = ROOT_SERVERS
+            .iter()
+            .map(|(name, v4, v6, _)| RootServer {
+                name: name.parse().expect("root server names are valid"),
+                addresses: vec![IpAddr::V4(*v4), IpAddr::V6(*v6)],
+            })
+            .collect();
+        RootHints { servers }
+    }
+}
+
+impl RootHints {
+    /// Reads a hints file such as https://www.internic.net/domain/named.root
+    pub fn from_file(path: impl AsRef<Path>) -> Result<RootHints, HintsError> {
+        let records = ZoneReader::new()
+            .with_origin(DomainName::root())
+            .read(path)?;
+        RootHints::from_hints(&records)
+    }
+    /// Parses hints in zone file format: NS records for the root, and the
+    /// A and AAAA records of the servers they name. Other records are ignored
+    pub fn parse(text: &str) -> Result<RootHints, HintsError> {
+        let records = ZoneReader::new()
+            .with_origin(DomainName::root())
+            .parse(text)?;
+        RootHints::from_hints(&records)
+    }
+    fn from_hints(records: &[Record]) -> Result<RootHints, HintsError> {
+        let hints = RootHints::from_records(records);
+        if hints.addresses().is_empty() {
+            return Err(HintsError::Empty);
+        }
+        Ok(hints)
+    }
+    /// Collects the root's NS records and the addresses of the servers
+    /// they name, as found in a hints file or a priming response
+    pub fn from_records(records: &[Record]) -> RootHints {
+        let mut servers: Vec<RootServer> = Vec::new();
+        for record in records.iter() {
+            if let (Kind::NS, Content::DomainName(name)) = (record.kind, &record.data) {
+                if record.name.is_root() && !servers.iter().any(|server| server.name == *name) {
+                    servers.push(RootServer {
+                        name: name.clone(),
+                        addresses: vec![],
+                    });
+                }
+            }
+        }
+        for record in records.iter() {
+            let address = match record.data {
+                Content::IPv4(ip) if record.kind == Kind::A => IpAddr::V4(ip),
+                Content::IPv6(ip) if record.kind == Kind::AAAA => IpAddr::V6(ip),
+                _ => continue,
+            };
+            if let Some(server) = servers.iter_mut().find(|server| server.name == record.name) {
+                if !server.addresses.contains(&address) {
+                    server.addresses.push(address);
+                }
+            }
+        }
+        RootHints { servers }
+    }
+    /// every address of every server, to query on the standard port
+    pub fn addresses(&self) -> Vec<SocketAddr> {
+        self.servers
+            .iter()
+            .flat_map(|server| server.addresses.iter())
+            .map(|ip| SocketAddr::new(*ip, DNS_PORT))
+            .collect()
+    }
+}
+
+impl Display for RootHints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for server in self.servers.iter() {
+            writeln!(f, ". NS {}", server.name)?;
+        }
+        for server in self.servers.iter() {
+            for address in server.addresses.iter() {
+                let kind = if address.is_ipv4() {
+                    Kind::A
+                } else {
+                    Kind::AAAA
+                };
+                writeln!(f, "{} {kind} {address}", server.name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum HintsError {
+    Zone(ZoneError),
+    /// no root server had an address
+    Empty,
+}
+
+impl Display for HintsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use HintsError::*;
+        match self {
+            Zone(e) => write!(f, "could not read hints: {e}"),
+            Empty => write!(f, "no root server addresses found"),
+        }
+    }
+}
+
+impl std::error::Error for HintsError {}
+
+impl From<ZoneError> for HintsError {
+    fn from(e: ZoneError) -> Self {
+        HintsError::Zone(e)
+    }
+}
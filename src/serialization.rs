@@ -1,3 +1,8 @@
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::domain_name::DomainName;
+
 fn pair(num: u16) -> (u8, u8) {
     let hi = ((num >> 8) & 0xff) as u8;
     let lo = (num & 0xff) as u8;
@@ -9,3 +14,108 @@ pub fn push_u16(buf: &mut Vec<u8>, num: u16) {
     buf.push(a);
     buf.push(b);
 }
+
+pub fn push_i32(buf: &mut Vec<u8>, num: i32) {
+    buf.extend_from_slice(&num.to_be_bytes());
+}
+
+/// writes bytes as length prefixed character-strings, splitting anything
+/// longer than the 255 bytes a single one can hold
+pub fn push_character_string(buf: &mut Vec<u8>, string: &[u8]) {
+    if string.is_empty() {
+        buf.push(0);
+    }
+    for chunk in string.chunks(u8::MAX as usize) {
+        buf.push(chunk.len() as u8);
+        buf.extend_from_slice(chunk);
+    }
+}
+
+pub fn push_collection<T: ToBytes>(buf: &mut Vec<u8>, items: &[T], names: &mut Compression) {
+    for item in items.iter() {
+        item.to_bytes(buf, names);
+    }
+}
+
+/// pointers can only address the first 14 bits worth of a message
+const MAX_POINTER: usize = 0b0011_1111_1111_1111;
+
+/// Remembers where each name suffix was written within a message, so that
+/// later occurrences can be replaced with a pointer (RFC 1035 4.1.4).
+/// Offsets are relative to the start of `buf`, so the same buffer must be
+/// used for the whole message.
+#[derive(Debug, Clone, Default)]
+pub struct Compression {
+    enabled: bool,
+    offsets: HashMap<DomainName, u16>,
+}
+
+impl Compression {
+    pub fn new() -> Compression {
+        Compression {
+            enabled: true,
+            offsets: HashMap::new(),
+        }
+    }
+    /// writes every name in full, for data where pointers are not allowed
+    pub fn disabled() -> Compression {
+        Compression {
+            enabled: false,
+            offsets: HashMap::new(),
+        }
+    }
+    /// finds a previously written suffix, names compare case-insensitively
+    pub fn find(&self, suffix: &DomainName) -> Option<u16> {
+        if !self.enabled {
+            return None;
+        }
+        self.offsets.get(suffix).copied()
+    }
+    pub fn insert(&mut self, suffix: &DomainName, offset: usize) {
+        if !self.enabled || offset > MAX_POINTER {
+            return;
+        }
+        self.offsets.entry(suffix.clone()).or_insert(offset as u16);
+    }
+}
+
+pub trait ToBytes {
+    fn to_bytes(&self, buf: &mut Vec<u8>, names: &mut Compression);
+}
+
+impl ToBytes for u8 {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        buf.push(*self);
+    }
+}
+
+impl ToBytes for char {
+    /// the inverse of `FromBytes for char`, only chars below 256 survive
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        buf.push(*self as u8);
+    }
+}
+
+impl ToBytes for i32 {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        push_i32(buf, *self);
+    }
+}
+
+impl ToBytes for u32 {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        buf.extend_from_slice(&self.to_be_bytes());
+    }
+}
+
+impl ToBytes for Ipv4Addr {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        buf.extend_from_slice(&self.octets());
+    }
+}
+
+impl ToBytes for Ipv6Addr {
+    fn to_bytes(&self, buf: &mut Vec<u8>, _names: &mut Compression) {
+        buf.extend_from_slice(&self.octets());
+    }
+}
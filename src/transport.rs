@@ -0,0 +1,155 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, UdpSocket};
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::deserialization::FromBytes;
+use crate::packet::{Header, Packet};
+use crate::resolver::ResolveError;
+use crate::serialization::push_u16;
+
+/// the dynamic port range from RFC 6335
+const EPHEMERAL_PORTS: RangeInclusive<u16> = 49152..=65535;
+/// how many random ports to try before letting the os pick one
+const BIND_ATTEMPTS: usize = 8;
+/// the largest message either transport can carry
+const MAX_MESSAGE: usize = u16::MAX as usize;
+
+/// How queries are sent to a server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Protocol {
+    /// udp, retrying over tcp whenever a response is truncated
+    #[default]
+    Udp,
+    /// always tcp
+    Tcp,
+}
+
+/// Sends a query using the given protocol and waits for the response
+pub fn exchange(
+    protocol: Protocol,
+    query: &Packet,
+    server: SocketAddr,
+    source: IpAddr,
+    timeout: Duration,
+) -> Result<Packet, ResolveError> {
+    match protocol {
+        Protocol::Tcp => exchange_tcp(query, server, timeout),
+        Protocol::Udp => {
+            let response = exchange_udp(query, server, source, timeout)?;
+            if response.flags.is_truncated() {
+                exchange_tcp(query, server, timeout)
+            } else {
+                Ok(response)
+            }
+        }
+    }
+}
+
+/// Binds a socket on a randomly chosen ephemeral port, so that responses
+/// are harder to spoof and concurrent lookups do not collide
+pub fn bind_udp(source: IpAddr) -> Result<UdpSocket, ResolveError> {
+    let mut rng = rand::thread_rng();
+    for _ in 0..BIND_ATTEMPTS {
+        let port = rng.gen_range(EPHEMERAL_PORTS);
+        match UdpSocket::bind(SocketAddr::new(source, port)) {
+            Ok(socket) => return Ok(socket),
+            Err(e) if e.kind() == ErrorKind::AddrInUse => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(UdpSocket::bind(SocketAddr::new(source, 0))?)
+}
+
+/// Whether this host has a route to a server from the given source
+/// address. Connecting a udp socket sends nothing, but fails straight away
+/// when the address family is not configured
+pub fn reachable(source: IpAddr, server: SocketAddr) -> bool {
+    UdpSocket::bind(SocketAddr::new(source, 0))
+        .and_then(|socket| socket.connect(server))
+        .is_ok()
+}
+
+/// Sends a query over udp and waits up to `timeout` for the response.
+/// Datagrams from any other address, or which do not carry the query's id
+/// and question, are discarded while waiting
+pub fn exchange_udp(
+    query: &Packet,
+    server: SocketAddr,
+    source: IpAddr,
+    timeout: Duration,
+) -> Result<Packet, ResolveError> {
+    let socket = bind_udp(source)?;
+    socket.send_to(&query.to_bytes(), server)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = vec![0u8; MAX_MESSAGE];
+    loop {
+        socket.set_read_timeout(Some(remaining(deadline)?))?;
+        let (count, addr) = socket.recv_from(&mut buf).map_err(io_error)?;
+        if addr != server {
+            continue;
+        }
+        let Ok(header) = Header::from_bytes(&buf[..count], &mut 0) else {
+            continue;
+        };
+        if header.id != query.id {
+            continue;
+        }
+        let Ok(response) = Packet::from_bytes(&buf[..count]) else {
+            continue;
+        };
+        if response.is_response_to(query) {
+            return Ok(response);
+        }
+    }
+}
+
+/// Sends a query over tcp, each message prefixed by its two byte length
+/// (RFC 1035 4.2.2). The standard library cannot choose the source address
+/// of a tcp connection, so the os picks one.
+pub fn exchange_tcp(
+    query: &Packet,
+    server: SocketAddr,
+    timeout: Duration,
+) -> Result<Packet, ResolveError> {
+    let deadline = Instant::now() + timeout;
+    let mut stream = TcpStream::connect_timeout(&server, timeout).map_err(io_error)?;
+    let message = query.to_bytes();
+    let mut buf = Vec::with_capacity(message.len() + 2);
+    push_u16(&mut buf, message.len() as u16);
+    buf.extend_from_slice(&message);
+    stream.set_write_timeout(Some(remaining(deadline)?))?;
+    stream.write_all(&buf).map_err(io_error)?;
+
+    let mut length = [0u8; 2];
+    stream.set_read_timeout(Some(remaining(deadline)?))?;
+    stream.read_exact(&mut length).map_err(io_error)?;
+    let mut buf = vec![0u8; u16::from_be_bytes(length) as usize];
+    stream.set_read_timeout(Some(remaining(deadline)?))?;
+    stream.read_exact(&mut buf).map_err(io_error)?;
+    let response = Packet::from_bytes(&buf)?;
+    if !response.is_response_to(query) {
+        return Err(ResolveError::Mismatch);
+    }
+    Ok(response)
+}
+
+fn remaining(deadline: Instant) -> Result<Duration, ResolveError> {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        Err(ResolveError::Timeout)
+    } else {
+        Ok(remaining)
+    }
+}
+
+/// read timeouts show up as different error kinds depending on the platform
+pub(crate) fn io_error(e: std::io::Error) -> ResolveError {
+    match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => ResolveError::Timeout,
+        _ => ResolveError::Io(e),
+    }
+}
@@ -0,0 +1,796 @@
+use std::fmt::Display;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+
+use crate::deserialization::FromBytes;
+use crate::domain_name::{DomainName, NameError};
+use crate::record::{Class, Content, Kind, Mx, Record, Soa, Srv};
+use crate::serialization::{Compression, ToBytes};
+
+/// how deeply `$INCLUDE` may nest, which also stops files including
+/// themselves
+const MAX_INCLUDE_DEPTH: usize = 8;
+/// the longest a character-string may be, RFC 1035 3.3
+const MAX_CHARACTER_STRING: usize = 255;
+
+/// Reads records from master files in the format of RFC 1035 5, with the
+/// `$TTL` directive from RFC 2308 4 and the generic data form from RFC 3597
+#[derive(Debug, Clone, Default)]
+pub struct ZoneReader {
+    origin: Option<DomainName>,
+    ttl: Option<u32>,
+}
+
+impl ZoneReader {
+    pub fn new() -> ZoneReader {
+        ZoneReader::default()
+    }
+    /// what `@` and relative names refer to until a `$ORIGIN` directive
+    pub fn with_origin(mut self, origin: DomainName) -> ZoneReader {
+        self.origin = Some(origin);
+        self
+    }
+    /// the ttl of records that give none, until a `$TTL` directive
+    pub fn with_ttl(mut self, ttl: u32) -> ZoneReader {
+        self.ttl = Some(ttl);
+        self
+    }
+    /// Parses the text of a zone file. Any `$INCLUDE` paths are relative to
+    /// the current directory
+    pub fn parse(&self, text: &str) -> Result<Vec<Record>, ZoneError> {
+        let mut state = State::new(self.origin.clone(), self.ttl, None, 0);
+        state.parse(text)?;
+        Ok(state.records)
+    }
+    /// Reads a zone file. Any `$INCLUDE` paths are relative to its directory
+    pub fn read(&self, path: impl AsRef<Path>) -> Result<Vec<Record>, ZoneError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let directory = path.parent().map(Path::to_path_buf);
+        let mut state = State::new(self.origin.clone(), self.ttl, directory, 0);
+        state.parse(&text)?;
+        Ok(state.records)
+    }
+}
+
+/// what carries over from one entry of a file to the next
+struct State {
+    origin: Option<DomainName>,
+    /// set by `$TTL`
+    default_ttl: Option<u32>,
+    owner: Option<DomainName>,
+    ttl: Option<u32>,
+    class: Class,
+    directory: Option<PathBuf>,
+    depth: usize,
+    records: Vec<Record>,
+}
+
+impl State {
+    fn new(
+        origin: Option<DomainName>,
+        default_ttl: Option<u32>,
+        directory: Option<PathBuf>,
+        depth: usize,
+    ) -> State {
+        State {
+            origin,
+            default_ttl,
+            owner: None,
+            ttl: None,
+            class: Class::Internet,
+            directory,
+            depth,
+            records: vec![],
+        }
+    }
+    fn parse(&mut self, text: &str) -> Result<(), ZoneError> {
+        for entry in entries(text)? {
+            match entry.tokens.first() {
+                Some(token)
+                    if !entry.blank_owner && !token.quoted && token.text.starts_with('$') =>
+                {
+                    self.directive(&entry)?
+                }
+                Some(_) => self.record(&entry)?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+    fn directive(&mut self, entry: &Entry) -> Result<(), ZoneError> {
+        let line = entry.line;
+        let bad = || ZoneError::BadDirective { line };
+        let arguments = &entry.tokens[1..];
+        match entry.tokens[0].text.to_ascii_uppercase().as_str() {
+            "$ORIGIN" => {
+                let [origin] = arguments else {
+                    return Err(bad());
+                };
+                self.origin = Some(self.name(origin, line)?);
+            }
+            "$TTL" => {
+                let [ttl] = arguments else {
+                    return Err(bad());
+                };
+                self.default_ttl = Some(parse_ttl(&ttl.text).ok_or(ZoneError::BadTtl { line })?);
+            }
+            "$INCLUDE" => {
+                let (path, origin) = match arguments {
+                    [path] => (path, self.origin.clone()),
+                    [path, origin] => (path, Some(self.name(origin, line)?)),
+                    _ => return Err(bad()),
+                };
+                self.include(Path::new(&path.text), origin, line)?;
+            }
+            _ => {
+                return Err(ZoneError::UnknownDirective {
+                    line,
+                    directive: entry.tokens[0].text.clone(),
+                })
+            }
+        }
+        Ok(())
+    }
+    /// an included file starts with its own owner and ttl, and changes to
+    /// its origin do not carry back, RFC 1035 5.1
+    fn include(
+        &mut self,
+        path: &Path,
+        origin: Option<DomainName>,
+        line: usize,
+    ) -> Result<(), ZoneError> {
+        if self.depth >= MAX_INCLUDE_DEPTH {
+            return Err(ZoneError::IncludeTooDeep { line });
+        }
+        let path = match &self.directory {
+            Some(directory) => directory.join(path),
+            None => path.to_path_buf(),
+        };
+        let wrap = |error: ZoneError| ZoneError::Include {
+            line,
+            path: path.clone(),
+            error: Box::new(error),
+        };
+        let text = std::fs::read_to_string(&path).map_err(|e| wrap(e.into()))?;
+        let directory = path.parent().map(Path::to_path_buf);
+        let mut included = State::new(origin, self.default_ttl, directory, self.depth + 1);
+        included.parse(&text).map_err(wrap)?;
+        self.records.append(&mut included.records);
+        Ok(())
+    }
+    fn record(&mut self, entry: &Entry) -> Result<(), ZoneError> {
+        let line = entry.line;
+        let mut tokens = entry.tokens.iter();
+        if !entry.blank_owner {
+            let owner = tokens.next().ok_or(ZoneError::MissingKind { line })?;
+            self.owner = Some(self.name(owner, line)?);
+        }
+        let name = self.owner.clone().ok_or(ZoneError::MissingOwner { line })?;
+        // the ttl and class may come in either order, and both be left out
+        let mut ttl = None;
+        let mut class = None;
+        let kind = loop {
+            let token = tokens.next().ok_or(ZoneError::MissingKind { line })?;
+            let numeric = token.text.bytes().all(|b| b.is_ascii_digit());
+            if let (None, Some(value)) = (ttl, parse_ttl(&token.text)) {
+                ttl = Some(value);
+            } else if let (None, Ok(value)) = (class, token.text.parse::<Class>()) {
+                class = Some(value);
+            } else if numeric {
+                return Err(ZoneError::BadTtl { line });
+            } else {
+                break token
+                    .text
+                    .parse::<Kind>()
+                    .map_err(|_| ZoneError::UnknownKind {
+                        line,
+                        kind: token.text.clone(),
+                    })?;
+            }
+        };
+        // an omitted ttl is the $TTL default, or failing that the last one
+        // given, RFC 2308 4
+        if let Some(ttl) = ttl {
+            self.ttl = Some(ttl);
+        }
+        let ttl = ttl
+            .or(self.default_ttl)
+            .or(self.ttl)
+            .ok_or(ZoneError::MissingTtl { line })?;
+        if ttl > i32::MAX as u32 {
+            return Err(ZoneError::BadTtl { line });
+        }
+        if let Some(class) = class {
+            self.class = class;
+        }
+        let data: Vec<&Token> = tokens.collect();
+        let data = self.data(kind, &data, line)?;
+        self.records.push(Record {
+            name,
+            kind,
+            class: self.class,
+            ttl: ttl as i32,
+            data,
+        });
+        Ok(())
+    }
+    fn data(&self, kind: Kind, tokens: &[&Token], line: usize) -> Result<Content, ZoneError> {
+        let bad = || ZoneError::BadData { line, kind };
+        use Kind::*;
+        if let Some(token) = tokens.first() {
+            if token.text == "\\#" && !token.quoted {
+                let text: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+                if kind == Kind::OPT {
+                    return Err(ZoneError::UnsupportedKind { line, kind });
+                }
+                let generic = Content::from_generic(&text.join(" ")).ok_or_else(bad)?;
+                // a known kind in the generic form means the same as in its
+                // usual form, RFC 3597 5, so decode it as from a message
+                let record = Record {
+                    name: DomainName::root(),
+                    kind,
+                    class: Class::Internet,
+                    ttl: 0,
+                    data: generic,
+                };
+                let mut buf = vec![];
+                record.to_bytes(&mut buf, &mut Compression::disabled());
+                let data = Record::from_bytes(&buf, &mut 0)
+                    .map(|record| record.data)
+                    .map_err(|_| bad())?;
+                // messages may carry data the usual form could not express,
+                // which the decoder keeps as is
+                return match (kind, &data) {
+                    (A, Content::IPv4(_)) => Ok(data),
+                    (A, _) => Err(bad()),
+                    (TXT, Content::Text(strings)) if strings.is_empty() => Err(bad()),
+                    (AAAA | NS | CNAME | DNAME | PTR | SOA | MX | SRV | TXT, Content::Other(_)) => {
+                        Err(bad())
+                    }
+                    _ => Ok(data),
+                };
+            }
+        }
+        let number = |token: &Token| token.text.parse::<u16>().map_err(|_| bad());
+        let time = |token: &Token| parse_ttl(&token.text).ok_or_else(bad);
+        let content = match (kind, tokens) {
+            (A, [ip]) => Content::IPv4(ip.text.parse::<Ipv4Addr>().map_err(|_| bad())?),
+            (AAAA, [ip]) => Content::IPv6(ip.text.parse::<Ipv6Addr>().map_err(|_| bad())?),
+            (NS | CNAME | DNAME | PTR, [name]) => Content::DomainName(self.name(name, line)?),
+            (SOA, [mname, rname, serial, refresh, retry, expire, minimum]) => Content::Soa(Soa {
+                mname: self.name(mname, line)?,
+                rname: self.name(rname, line)?,
+                serial: serial.text.parse().map_err(|_| bad())?,
+                refresh: time(refresh)?,
+                retry: time(retry)?,
+                expire: time(expire)?,
+                minimum: time(minimum)?,
+            }),
+            (MX, [preference, exchange]) => Content::Mx(Mx {
+                preference: number(preference)?,
+                exchange: self.name(exchange, line)?,
+            }),
+            (SRV, [priority, weight, port, target]) => Content::Srv(Srv {
+                priority: number(priority)?,
+                weight: number(weight)?,
+                port: number(port)?,
+                target: self.name(target, line)?,
+            }),
+            (TXT, [_, ..]) => {
+                let strings = tokens
+                    .iter()
+                    .map(|token| unescape(&token.text))
+                    .collect::<Option<Vec<Vec<u8>>>>()
+                    .ok_or_else(bad)?;
+                if strings.iter().any(|s| s.len() > MAX_CHARACTER_STRING) {
+                    return Err(bad());
+                }
+                Content::Text(strings)
+            }
+            (A | AAAA | NS | CNAME | DNAME | PTR | SOA | MX | SRV | TXT, _) => return Err(bad()),
+            // anything else can only be written in the generic form
+            _ => return Err(ZoneError::UnsupportedKind { line, kind }),
+        };
+        Ok(content)
+    }
+    /// reads a name, completing relative names and `@` with the origin
+    fn name(&self, token: &Token, line: usize) -> Result<DomainName, ZoneError> {
+        let origin = || self.origin.clone().ok_or(ZoneError::MissingOrigin { line });
+        if token.text == "@" && !token.quoted {
+            return origin();
+        }
+        let name: DomainName = token
+            .text
+            .parse()
+            .map_err(|error| ZoneError::BadName { line, error })?;
+        if name.is_fully_qualified() {
+            return Ok(name);
+        }
+        name.append(&origin()?)
+            .map_err(|error| ZoneError::BadName { line, error })
+    }
+}
+
+/// a ttl in seconds, or with units as in `1h30m`, which BIND also accepts
+fn parse_ttl(text: &str) -> Option<u32> {
+    if text.is_empty() {
+        return None;
+    }
+    let mut total: u32 = 0;
+    let mut value: Option<u32> = None;
+    for c in text.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            value = Some(value.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            'w' => 604_800,
+            _ => return None,
+        };
+        total = total.checked_add(value.take()?.checked_mul(unit)?)?;
+    }
+    total.checked_add(value.unwrap_or(0))
+}
+
+/// the bytes of a character-string, with `\X` and `\DDD` escapes replaced
+fn unescape(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.bytes();
+    while let Some(byte) = rest.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let escaped = rest.next()?;
+        if escaped.is_ascii_digit() {
+            let digits = [escaped, rest.next()?, rest.next()?];
+            if !digits.iter().all(u8::is_ascii_digit) {
+                return None;
+            }
+            let value = digits
+                .iter()
+                .fold(0u16, |value, digit| value * 10 + (digit - b'0') as u16);
+            bytes.push(u8::try_from(value).ok()?);
+        } else {
+            bytes.push(escaped);
+        }
+    }
+    Some(bytes)
+}
+
+/// A word of an entry, with any escapes left in place
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    /// written in double quotes, so that it may hold spaces
+    quoted: bool,
+}
+
+/// One record or directive, which parentheses may spread over several lines
+#[derive(Debug, Clone)]
+struct Entry {
+    /// the line the entry starts on
+    line: usize,
+    /// the line started with blank space, so the record has the previous
+    /// owner
+    blank_owner: bool,
+    tokens: Vec<Token>,
+}
+
+/// splits a file into entries, dropping comments
+fn entries(text: &str) -> Result<Vec<Entry>, ZoneError> {
+    let mut entries = vec![];
+    let mut line = 1;
+    let mut entry: Option<Entry> = None;
+    let mut token: Option<Token> = None;
+    // the line an open parenthesis is on
+    let mut open: Option<usize> = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let current = entry.get_or_insert_with(|| Entry {
+            line,
+            blank_owner: c == ' ' || c == '\t',
+            tokens: vec![],
+        });
+        match c {
+            '\n' | ' ' | '\t' | '\r' | ';' | '(' | ')' | '"' => {
+                current.tokens.extend(token.take());
+            }
+            _ => {}
+        }
+        match c {
+            '\n' => {
+                line += 1;
+                if open.is_none() {
+                    entries.extend(entry.take());
+                }
+            }
+            ' ' | '\t' | '\r' => {}
+            ';' => while chars.next_if(|c| *c != '\n').is_some() {},
+            '(' => {
+                if open.is_some() {
+                    return Err(ZoneError::Parenthesis { line });
+                }
+                open = Some(line);
+            }
+            ')' => {
+                if open.take().is_none() {
+                    return Err(ZoneError::Parenthesis { line });
+                }
+            }
+            '"' => {
+                let start = line;
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(ZoneError::Unterminated { line: start }),
+                        Some('"') => break,
+                        Some('\\') => {
+                            let escaped = chars
+                                .next()
+                                .ok_or(ZoneError::Unterminated { line: start })?;
+                            line += (escaped == '\n') as usize;
+                            text.push('\\');
+                            text.push(escaped);
+                        }
+                        Some(c) => {
+                            line += (c == '\n') as usize;
+                            text.push(c);
+                        }
+                    }
+                }
+                current.tokens.push(Token { text, quoted: true });
+            }
+            _ => {
+                let word = token.get_or_insert_with(|| Token {
+                    text: String::new(),
+                    quoted: false,
+                });
+                word.text.push(c);
+                if c == '\\' {
+                    if let Some(escaped) = chars.next() {
+                        line += (escaped == '\n') as usize;
+                        word.text.push(escaped);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(line) = open {
+        return Err(ZoneError::Unterminated { line });
+    }
+    if let Some(mut last) = entry {
+        last.tokens.extend(token);
+        entries.push(last);
+    }
+    Ok(entries)
+}
+
+/// Why a zone file could not be read, with the line the problem is on
+#[derive(Debug)]
+pub enum ZoneError {
+    Io(std::io::Error),
+    /// an included file could not be read
+    Include {
+        line: usize,
+        path: PathBuf,
+        error: Box<ZoneError>,
+    },
+    IncludeTooDeep {
+        line: usize,
+    },
+    UnknownDirective {
+        line: usize,
+        directive: String,
+    },
+    /// a directive had the wrong number of arguments
+    BadDirective {
+        line: usize,
+    },
+    /// a quoted string or parenthesis was still open at the end of the file
+    Unterminated {
+        line: usize,
+    },
+    /// a nested parenthesis, or one closed without being opened
+    Parenthesis {
+        line: usize,
+    },
+    /// the first record left out its owner
+    MissingOwner {
+        line: usize,
+    },
+    /// `@` or a relative name was used with no origin set
+    MissingOrigin {
+        line: usize,
+    },
+    /// no ttl was given and there was none to inherit
+    MissingTtl {
+        line: usize,
+    },
+    MissingKind {
+        line: usize,
+    },
+    BadTtl {
+        line: usize,
+    },
+    BadName {
+        line: usize,
+        error: NameError,
+    },
+    UnknownKind {
+        line: usize,
+        kind: String,
+    },
+    /// a kind that can only be written in the generic form, or not at all
+    UnsupportedKind {
+        line: usize,
+        kind: Kind,
+    },
+    /// data that does not fit the kind of record
+    BadData {
+        line: usize,
+        kind: Kind,
+    },
+}
+
+impl ZoneError {
+    /// the line in the outermost file, if the error came from a line
+    pub fn line(&self) -> Option<usize> {
+        use ZoneError::*;
+        match self {
+            Io(_) => None,
+            Include { line, .. }
+            | IncludeTooDeep { line }
+            | UnknownDirective { line, .. }
+            | BadDirective { line }
+            | Unterminated { line }
+            | Parenthesis { line }
+            | MissingOwner { line }
+            | MissingOrigin { line }
+            | MissingTtl { line }
+            | MissingKind { line }
+            | BadTtl { line }
+            | BadName { line, .. }
+            | UnknownKind { line, .. }
+            | UnsupportedKind { line, .. }
+            | BadData { line, .. } => Some(*line),
+        }
+    }
+}
+
+impl Display for ZoneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ZoneError::*;
+        match self {
+            Io(e) => write!(f, "could not read zone: {e}"),
+            Include { line, path, error } => {
+                write!(f, "line {line}: in {}: {error}", path.display())
+            }
+            IncludeTooDeep { line } => write!(f, "line {line}: includes nested too deeply"),
+            UnknownDirective { line, directive } => {
+                write!(f, "line {line}: unknown directive {directive}")
+            }
+            BadDirective { line } => write!(f, "line {line}: wrong arguments for directive"),
+            Unterminated { line } => write!(f, "line {line}: quote or parenthesis never closed"),
+            Parenthesis { line } => write!(f, "line {line}: unbalanced parenthesis"),
+            MissingOwner { line } => write!(f, "line {line}: no previous owner to inherit"),
+            MissingOrigin { line } => write!(f, "line {line}: relative name with no origin"),
+            MissingTtl { line } => write!(f, "line {line}: no ttl given or inherited"),
+            MissingKind { line } => write!(f, "line {line}: no record type"),
+            BadTtl { line } => write!(f, "line {line}: invalid ttl"),
+            BadName { line, error } => write!(f, "line {line}: invalid name: {error}"),
+            UnknownKind { line, kind } => write!(f, "line {line}: unknown record type {kind}"),
+            UnsupportedKind { line, kind } => {
+                write!(f, "line {line}: {kind} records cannot be read from text")
+            }
+            BadData { line, kind } => write!(f, "line {line}: invalid data for {kind} record"),
+        }
+    }
+}
+
+impl std::error::Error for ZoneError {}
+
+impl From<std::io::Error> for ZoneError {
+    fn from(e: std::io::Error) -> Self {
+        ZoneError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<Record> {
+        ZoneReader::new().parse(text).unwrap()
+    }
+
+    fn error(text: &str) -> ZoneError {
+        ZoneReader::new().parse(text).unwrap_err()
+    }
+
+    fn names(records: &[Record]) -> Vec<String> {
+        records.iter().map(|r| r.name.to_string()).collect()
+    }
+
+    fn strings(record: &Record) -> Vec<Vec<u8>> {
+        match &record.data {
+            Content::Text(strings) => strings.clone(),
+            data => panic!("not text: {data:?}"),
+        }
+    }
+
+    #[test]
+    fn origin_at_and_relative_names() {
+        let records = parse(
+            "$ORIGIN example.com.\n\
+             @ 60 A 192.0.2.1\n\
+             www 60 CNAME @\n\
+             mail.example.net. 60 MX 10 mx\n\
+             $ORIGIN sub\n\
+             host 60 A 192.0.2.2\n",
+        );
+        assert_eq!(
+            names(&records),
+            [
+                "example.com.",
+                "www.example.com.",
+                "mail.example.net.",
+                "host.sub.example.com."
+            ]
+        );
+        assert_eq!(records[1].data.to_string(), "example.com.");
+        assert_eq!(records[2].data.to_string(), "10 mx.example.com.");
+    }
+
+    #[test]
+    fn ttl_directive_and_inheritance() {
+        // without $TTL, a record takes the last ttl given
+        let records = parse("a. 100 A 192.0.2.1\nb. A 192.0.2.2\n");
+        assert_eq!(records[1].ttl, 100);
+        // with it, $TTL wins over the last one given
+        let records = parse(
+            "a. 100 A 192.0.2.1\n\
+             $TTL 1h\n\
+             b. A 192.0.2.2\n\
+             c. 200 A 192.0.2.3\n\
+             d. A 192.0.2.4\n",
+        );
+        let ttls: Vec<i32> = records.iter().map(|r| r.ttl).collect();
+        assert_eq!(ttls, [100, 3600, 200, 3600]);
+        let records = ZoneReader::new()
+            .with_ttl(30)
+            .parse("a. A 192.0.2.1\n")
+            .unwrap();
+        assert_eq!(records[0].ttl, 30);
+    }
+
+    #[test]
+    fn ttl_and_class_in_either_order() {
+        let records = parse(
+            "a. 60 CH TXT x\n\
+             b. HS 1w2d TXT x\n\
+             c. TXT x\n",
+        );
+        assert_eq!((records[0].ttl, records[0].class), (60, Class::Chaos));
+        assert_eq!((records[1].ttl, records[1].class), (777_600, Class::Hesiod));
+        // both carry over when left out
+        assert_eq!((records[2].ttl, records[2].class), (777_600, Class::Hesiod));
+    }
+
+    #[test]
+    fn blank_owner_continues_previous() {
+        let records = parse(
+            "a.example. 60 A 192.0.2.1\n\
+             \x20 60 AAAA 2001:db8::1\n\
+             \tMX 10 mx.example.\n\
+             b.example. 60 A 192.0.2.2\n",
+        );
+        assert_eq!(
+            names(&records),
+            ["a.example.", "a.example.", "a.example.", "b.example."]
+        );
+    }
+
+    #[test]
+    fn parentheses_span_lines() {
+        let records = parse(
+            "example. 60 SOA ns.example. admin.example. ( ; comment with ( and )\n\
+             \x20   2024010101 ; serial\n\
+             \x20   1h 15m     ; refresh ) retry (\n\
+             \x20   1w\n\
+             \x20   300 )\n\
+             www.example. 60 A 192.0.2.1\n",
+        );
+        assert_eq!(records.len(), 2);
+        let Content::Soa(soa) = &records[0].data else {
+            panic!("not an SOA: {:?}", records[0].data);
+        };
+        assert_eq!(
+            (soa.serial, soa.refresh, soa.retry, soa.expire, soa.minimum),
+            (2024010101, 3600, 900, 604_800, 300)
+        );
+        assert_eq!(records[1].name.to_string(), "www.example.");
+    }
+
+    #[test]
+    fn quoted_strings_hide_specials() {
+        let records = parse("a. 60 TXT \"semi;colon (paren\" \"two words\" plain\n");
+        assert_eq!(
+            strings(&records[0]),
+            [&b"semi;colon (paren"[..], b"two words", b"plain"]
+        );
+    }
+
+    #[test]
+    fn decimal_escapes() {
+        let records = parse("a\\032b.example. 60 TXT \"\\065\\066\" x\\059y \\\"\n");
+        assert_eq!(records[0].name.labels()[0], b"a b");
+        assert_eq!(strings(&records[0]), [&b"AB"[..], b"x;y", b"\""]);
+        assert!(matches!(
+            error("a. 60 TXT \"\\256\""),
+            ZoneError::BadData { line: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn generic_data() {
+        let records = parse(
+            "a. 60 A \\# 4 c0000201\n\
+             b. 60 TYPE731 \\# 3 ab ( cd\n\
+             ef )\n\
+             c. 60 CLASS1 TYPE1 \\# 4 c0000202\n",
+        );
+        assert_eq!(records[0].data.to_string(), "192.0.2.1");
+        assert_eq!(records[1].kind, Kind::Unknown(731));
+        assert_eq!(records[2].kind, Kind::A);
+        assert_eq!(records[2].class, Class::Internet);
+        assert_eq!(records[2].data.to_string(), "192.0.2.2");
+        assert!(matches!(&records[1].data, Content::Other(data) if data == &[0xab, 0xcd, 0xef]));
+        assert!(matches!(
+            error("a. 60 A \\# 3 c00002"),
+            ZoneError::BadData { line: 1, .. }
+        ));
+        assert!(matches!(
+            error("a. 60 MX \\# 0"),
+            ZoneError::BadData { line: 1, .. }
+        ));
+        assert!(matches!(
+            error("a. 60 TXT \\# 0"),
+            ZoneError::BadData { line: 1, .. }
+        ));
+        assert!(matches!(
+            error("\n\na. 60 A \\# 2 c0"),
+            ZoneError::BadData { line: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn errors_name_their_line() {
+        let cases: &[(&str, usize)] = &[
+            ("$TTL 60\n\n$BOGUS x", 3),
+            ("$TTL 60\n$ORIGIN", 2),
+            ("$TTL 60\na. TXT \"\nb. A 192.0.2.1\n", 2),
+            ("$TTL 60\na. A ( 192.0.2.1\n\nb. A 192.0.2.2\n", 2),
+            ("$TTL 60\n\na. A ) 192.0.2.1", 3),
+            ("$TTL 60\n 60 A 192.0.2.1", 2),
+            ("$TTL 60\n\n\nwww A 192.0.2.1", 4),
+            ("a. 60 A 192.0.2.1\nb. 60", 2),
+            ("\nb. A 192.0.2.1", 2),
+            ("a. 99999999999 A 192.0.2.1", 1),
+            ("$TTL 60\na..b. A 192.0.2.1", 2),
+            ("$TTL 60\n\na. BOGUS x", 3),
+            ("$TTL 60\n\na. HINFO x y", 3),
+            ("$TTL 60\n(\n)\na. A 192.0.2.300", 4),
+        ];
+        for (text, line) in cases {
+            let error = error(text);
+            assert_eq!(error.line(), Some(*line), "{text:?} gave {error}");
+            assert!(error.to_string().starts_with(&format!("line {line}: ")));
+        }
+    }
+}